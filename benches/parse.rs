@@ -0,0 +1,78 @@
+use std::io::sink;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::indexed::IndexedHostsFile;
+use hosts::hostsfile::parse::ParsedLine;
+use hosts::hostsfile::HostsFile;
+
+const LINE_COUNT: usize = 500_000;
+
+fn get_blocklist_lines() -> Vec<String> {
+    let mut lines = Vec::with_capacity(LINE_COUNT);
+    for index in 0..LINE_COUNT {
+        if index % 1000 == 0 {
+            lines.push(format!("# section {}", index / 1000));
+        } else {
+            lines.push(format!(
+                "0.0.0.0 ads{}.tracker{}.example.com",
+                index,
+                index % 97
+            ));
+        }
+    }
+    lines
+}
+
+fn bench_large_file(c: &mut Criterion) {
+    let lines = get_blocklist_lines();
+    let mut group = c.benchmark_group("500k lines");
+    group.sample_size(10);
+    group.bench_function("read_from_lines", |b| {
+        b.iter(|| HostsFile::read_from_lines(lines.iter().cloned().map(Ok)).unwrap())
+    });
+    group.bench_function("stream_to", |b| {
+        b.iter(|| {
+            HostsFile::stream_to(
+                lines.iter().cloned().map(Ok),
+                &mut sink(),
+                |line| match line {
+                    ParsedLine::Entry(_) => Some(line),
+                    _ => None,
+                },
+            )
+            .unwrap()
+        })
+    });
+    group.bench_function("render", |b| {
+        let file = HostsFile::read_from_lines(lines.iter().cloned().map(Ok)).unwrap();
+        b.iter(|| file.render())
+    });
+    group.finish();
+}
+
+fn bench_batch_import(c: &mut Criterion) {
+    let lines = get_blocklist_lines();
+    let imports: Vec<HostsEntry> = (0..20_000)
+        .map(|index| {
+            let host = format!("import{}.tracker{}.example.com", index, index % 97);
+            HostsEntry::parse("127.0.0.1", &host).unwrap()
+        })
+        .collect();
+    let mut group = c.benchmark_group("20k imports into 500k lines");
+    group.sample_size(10);
+    group.bench_function("IndexedHostsFile::add", |b| {
+        b.iter(|| {
+            let file = HostsFile::read_from_lines(lines.iter().cloned().map(Ok)).unwrap();
+            let mut indexed = IndexedHostsFile::from(file);
+            for entry in imports.iter() {
+                indexed.add(entry);
+            }
+            indexed.into_file()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_large_file, bench_batch_import);
+criterion_main!(benches);
@@ -0,0 +1,301 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+use crate::hostsfile::elevate::Elevation;
+use crate::hostsfile::journal::JournalRecord;
+use crate::hostsfile::parse::ParsedLine;
+use crate::hostsfile::HostsFile;
+use crate::validation::Validation;
+
+/// Who changed the hosts file, when, with which command, and how. Unlike
+/// the journal, which undo rewrites, the audit log is only ever appended to,
+/// one record per line as JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Seconds since the Unix epoch.
+    pub time: u64,
+    /// The name of `uid` in the password database.
+    pub user: Option<String>,
+    /// The user the environment says ran the change, such as the one who
+    /// ran `sudo`. Anyone can set it, so it is only a claim.
+    pub claimed_user: Option<String>,
+    pub uid: Option<u32>,
+    pub command: Vec<String>,
+    pub operation: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    #[serde(with = "hex_hash")]
+    pub before_hash: u64,
+    #[serde(with = "hex_hash")]
+    pub after_hash: u64,
+}
+
+impl AuditRecord {
+    /// Describes a change made now by this process, as `operation`.
+    pub fn new(operation: &str, record: &JournalRecord) -> Self {
+        let uid = AuditRecord::current_uid();
+        AuditRecord {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default(),
+            user: uid.and_then(AuditRecord::user_name),
+            claimed_user: ["SUDO_USER", "USER", "USERNAME"]
+                .iter()
+                .find_map(|variable| std::env::var(variable).ok()),
+            uid,
+            command: std::env::args().collect(),
+            operation: String::from(operation),
+            before: record.before.clone(),
+            after: record.after.clone(),
+            before_hash: record.before_hash,
+            after_hash: record.after_hash,
+        }
+    }
+
+    /// The change in the other direction, as made by undo.
+    pub fn reversed(self) -> Self {
+        AuditRecord {
+            before: self.after,
+            after: self.before,
+            before_hash: self.after_hash,
+            after_hash: self.before_hash,
+            ..self
+        }
+    }
+
+    #[cfg(unix)]
+    fn current_uid() -> Option<u32> {
+        // SAFETY: getuid cannot fail and has no side effects.
+        Some(unsafe { libc::getuid() })
+    }
+
+    #[cfg(not(unix))]
+    fn current_uid() -> Option<u32> {
+        None
+    }
+
+    #[cfg(unix)]
+    fn user_name(uid: u32) -> Option<String> {
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut buffer = vec![0 as libc::c_char; 4096];
+        let mut found: *mut libc::passwd = std::ptr::null_mut();
+        // SAFETY: every pointer points at memory that outlives the call, and
+        // `buffer.len()` is the size of the buffer.
+        let status = unsafe {
+            libc::getpwuid_r(
+                uid,
+                &mut passwd,
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                &mut found,
+            )
+        };
+        if status != 0 || found.is_null() {
+            return None;
+        }
+        // SAFETY: on success `pw_name` points at a C string in `buffer`.
+        let name = unsafe { std::ffi::CStr::from_ptr(passwd.pw_name) };
+        Some(name.to_string_lossy().into_owned())
+    }
+
+    #[cfg(not(unix))]
+    fn user_name(_uid: u32) -> Option<String> {
+        None
+    }
+
+    /// Whether an entry for `host` is among the lines before or after the
+    /// change, enabled or disabled.
+    pub fn touches(&self, host: &str) -> bool {
+        let host = match HostsEntry::parse_host(host) {
+            Ok(host) => host,
+            Err(_) => return false,
+        };
+        self.before.iter().chain(self.after.iter()).any(|line| {
+            let entry = match HostsFile::parse_line_with(line, Validation::Permissive) {
+                ParsedLine::Entry(entry) => Some(entry),
+                line => line.disabled_entry(),
+            };
+            entry.is_some_and(|entry| entry.has_host(&host))
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        // Only strings and numbers, which always serialize.
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_json(line: &str) -> Result<Self, HostsError> {
+        serde_json::from_str(line)
+            .map_err(|error| HostsError::Parse(format!("invalid audit record: {}", error)))
+    }
+}
+
+/// Hashes are written as 16 hex digits, as in the journal, since JSON
+/// readers tend to lose precision on numbers that large.
+mod hex_hash {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(hash: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:016x}", hash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        u64::from_str_radix(&hex, 16).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Which records `hosts log` shows. Times are inclusive.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AuditFilter {
+    pub host: Option<String>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        self.since.is_none_or(|since| record.time >= since)
+            && self.until.is_none_or(|until| record.time <= until)
+            && self.host.as_ref().is_none_or(|host| record.touches(host))
+    }
+}
+
+pub struct Audit;
+
+impl Audit {
+    pub fn path_for(hosts_path: &Path) -> PathBuf {
+        let mut file_name = hosts_path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".audit");
+        hosts_path.with_file_name(file_name)
+    }
+
+    /// Appends a record to the log next to the hosts file, through the
+    /// elevation command if it may not be written directly.
+    pub fn append(hosts_path: &Path, record: &AuditRecord) -> Result<(), HostsError> {
+        let line = format!("{}\n", record.to_json());
+        HostsFile::append_bytes(
+            Audit::path_for(hosts_path),
+            line.as_bytes(),
+            Elevation::from_env().as_ref(),
+        )
+    }
+
+    /// Reads the records for `hosts_path` that match `filter`, oldest first,
+    /// along with the 1-based numbers of the lines that are not records,
+    /// such as one cut short by a full disk. Those are skipped, so one bad
+    /// line does not hide the rest of the log.
+    pub fn read(
+        hosts_path: &Path,
+        filter: &AuditFilter,
+    ) -> Result<(Vec<AuditRecord>, Vec<usize>), HostsError> {
+        let file = match File::open(Audit::path_for(hosts_path)) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Ok((Vec::new(), Vec::new()))
+            }
+            Err(error) => return Err(HostsError::from(error)),
+        };
+        let mut records = Vec::new();
+        let mut skipped = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match AuditRecord::from_json(&line) {
+                Ok(record) if filter.matches(&record) => records.push(record),
+                Ok(_) => {}
+                Err(_) => skipped.push(index + 1),
+            }
+        }
+        Ok((records, skipped))
+    }
+}
+
+/// Parses `1700000000`, `2024-01-31` or `2024-01-31T12:00:00`, in UTC, into
+/// seconds since the Unix epoch.
+pub fn parse_time(value: &str) -> Result<u64, HostsError> {
+    let invalid = || {
+        HostsError::Parse(format!(
+            "invalid time {}, expected seconds since the epoch or YYYY-MM-DD[THH:MM:SS]",
+            value
+        ))
+    };
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(seconds);
+    }
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, time),
+        None => (value, "00:00:00"),
+    };
+    let numbers = |text: &str, separator: char| -> Result<Vec<u64>, HostsError> {
+        let numbers = text
+            .split(separator)
+            .map(|number| number.parse::<u64>().map_err(|_| invalid()))
+            .collect::<Result<Vec<u64>, HostsError>>()?;
+        match numbers.len() {
+            3 => Ok(numbers),
+            _ => Err(invalid()),
+        }
+    };
+    let (date, time) = (
+        numbers(date, '-')?,
+        numbers(time.trim_end_matches('Z'), ':')?,
+    );
+    let (year, month, day) = (date[0], date[1], date[2]);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return Err(invalid());
+    }
+    if time[0] > 23 || time[1] > 59 || time[2] > 59 {
+        return Err(invalid());
+    }
+    Ok(days_from_civil(year, month, day) * 86400 + time[0] * 3600 + time[1] * 60 + time[2])
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM:SS` in UTC.
+pub fn format_time(time: u64) -> String {
+    let (year, month, day) = civil_from_days(time / 86400);
+    let seconds = time % 86400;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+// Howard Hinnant's conversions between dates and days since 1970-01-01,
+// limited to dates from the epoch on.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
@@ -0,0 +1,64 @@
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+/// An IPv4 network in CIDR notation, such as `10.0.0.0/8`. A bare address
+/// is read as a `/32` network containing just that address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ipv4Cidr {
+    pub network: Ipv4Addr,
+    pub prefix: u8,
+}
+
+impl Ipv4Cidr {
+    pub fn new(ip: Ipv4Addr, prefix: u8) -> Result<Self, String> {
+        if prefix > 32 {
+            return Err(format!("invalid prefix length {}", prefix));
+        }
+        let mask = Ipv4Cidr::mask_of(prefix);
+        Ok(Ipv4Cidr {
+            network: Ipv4Addr::from(u32::from(ip) & mask),
+            prefix,
+        })
+    }
+
+    pub fn mask(&self) -> u32 {
+        Ipv4Cidr::mask_of(self.prefix)
+    }
+
+    pub fn contains(&self, ip: &Ipv4Addr) -> bool {
+        u32::from(*ip) & self.mask() == u32::from(self.network)
+    }
+
+    fn mask_of(prefix: u8) -> u32 {
+        match prefix {
+            0 => 0,
+            prefix => u32::MAX << (32 - prefix),
+        }
+    }
+}
+
+impl FromStr for Ipv4Cidr {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (ip, prefix) = match value.split_once('/') {
+            Some((ip, prefix)) => (ip, prefix),
+            None => (value, "32"),
+        };
+        let ip = match ip.parse::<Ipv4Addr>() {
+            Ok(ip) => ip,
+            Err(error) => return Err(error.to_string()),
+        };
+        let prefix = match prefix.parse::<u8>() {
+            Ok(prefix) => prefix,
+            Err(_) => return Err(format!("invalid prefix length {}", prefix)),
+        };
+        Ipv4Cidr::new(ip, prefix)
+    }
+}
+
+impl std::fmt::Display for Ipv4Cidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix)
+    }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::hostsfile::parse::ParsedLine;
+use crate::hostsfile::HostsFile;
+
+/// Shells that completion scripts can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl std::str::FromStr for Shell {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "unknown shell {}, expected bash, zsh or fish",
+                value
+            )),
+        }
+    }
+}
+
+/// What the positional arguments of a subcommand complete to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Candidates {
+    Nothing,
+    /// Hostnames and IPs of the enabled entries.
+    Entries,
+    /// Hostnames and IPs of the disabled entries.
+    DisabledEntries,
+    /// Hostnames of the enabled entries.
+    Hosts,
+    /// IPs of the enabled entries.
+    Ips,
+    Shells,
+    /// `use` and `list`, then the names of the profiles after `use`.
+    Profiles,
+}
+
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub options: &'static [&'static str],
+    pub candidates: Candidates,
+}
+
+/// Every subcommand with its options, which the completions offer. Keep it
+/// in step with the subcommands of the binary.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "list",
+        options: &["--cidr", "--by-ip", "--validation", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "who",
+        options: &["--validation", "--path"],
+        candidates: Candidates::Ips,
+    },
+    CommandSpec {
+        name: "resolve",
+        options: &["--path"],
+        candidates: Candidates::Hosts,
+    },
+    CommandSpec {
+        name: "add",
+        options: &["--stdin", "--force", "--validation", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "remove",
+        options: &["--cidr", "--path"],
+        candidates: Candidates::Entries,
+    },
+    CommandSpec {
+        name: "rename",
+        options: &["--merge", "--validation", "--force", "--path"],
+        candidates: Candidates::Hosts,
+    },
+    CommandSpec {
+        name: "retarget",
+        options: &["--force", "--path"],
+        candidates: Candidates::Ips,
+    },
+    CommandSpec {
+        name: "render",
+        options: &["--env", "--force", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "disable",
+        options: &["--cidr", "--path"],
+        candidates: Candidates::Entries,
+    },
+    CommandSpec {
+        name: "enable",
+        options: &["--cidr", "--force", "--path"],
+        candidates: Candidates::DisabledEntries,
+    },
+    CommandSpec {
+        name: "undo",
+        options: &["--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "redo",
+        options: &["--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "history",
+        options: &["--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "log",
+        options: &["--host", "--since", "--until", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "fmt",
+        options: &["--check", "--sort", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "compact",
+        options: &["--limit", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "expand",
+        options: &["--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "profile",
+        options: &["--force", "--path"],
+        candidates: Candidates::Profiles,
+    },
+    CommandSpec {
+        name: "tui",
+        options: &["--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "export",
+        options: &["--to", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "import",
+        options: &["--from", "--validation", "--force", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "serve",
+        options: &["--listen", "--path"],
+        candidates: Candidates::Nothing,
+    },
+    CommandSpec {
+        name: "completions",
+        options: &[],
+        candidates: Candidates::Shells,
+    },
+];
+
+/// The commands of this build: `tui` is left out without the `tui` feature.
+pub fn commands() -> impl Iterator<Item = &'static CommandSpec> {
+    COMMANDS
+        .iter()
+        .filter(|command| cfg!(feature = "tui") || command.name != "tui")
+}
+
+/// Name of the hidden subcommand the generated scripts call to complete.
+pub const COMPLETE_COMMAND: &str = "__complete";
+
+/// The completion script for `shell`. The scripts pass the words typed so
+/// far to `hosts __complete`, so hostnames come from the file in use.
+pub fn script(shell: Shell) -> String {
+    match shell {
+        Shell::Bash => String::from(
+            r#"_hosts() {
+    local IFS=$'\n'
+    COMPREPLY=($(hosts __complete "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+complete -o default -F _hosts hosts
+"#,
+        ),
+        Shell::Zsh => String::from(
+            r#"#compdef hosts
+_hosts() {
+    local -a candidates
+    candidates=("${(@f)$(hosts __complete "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+    if [[ -n "${candidates[1]}" ]]; then
+        compadd -a candidates
+    else
+        _files
+    fi
+}
+compdef _hosts hosts
+"#,
+        ),
+        Shell::Fish => String::from(
+            r#"complete -c hosts -f -a '(hosts __complete (commandline -opc)[2..-1] (commandline -ct) 2>/dev/null)'
+"#,
+        ),
+    }
+}
+
+/// Completes the last of `words`, which are the arguments typed after the
+/// binary name. Hostnames and IPs are read from the file given with
+/// `--path`, or from the system hosts file.
+pub fn complete(words: &[String]) -> Vec<String> {
+    let (current, typed) = match words.split_last() {
+        Some((current, typed)) => (current.as_str(), typed),
+        None => ("", words),
+    };
+    let matching = |candidates: Vec<String>| -> Vec<String> {
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(current))
+            .collect()
+    };
+    let mut command = None;
+    let mut typed_words = typed.iter();
+    while let Some(word) = typed_words.next() {
+        if word.starts_with('-') {
+            // Skip the value of options such as the top level --path.
+            if !is_switch(word) {
+                typed_words.next();
+            }
+            continue;
+        }
+        command = commands().find(|command| command.name == word);
+        break;
+    }
+    let after_option = typed
+        .last()
+        .is_some_and(|word| word.starts_with('-') && !is_switch(word));
+    let command = match command {
+        Some(command) => command,
+        None if after_option => return Vec::new(),
+        None if current.starts_with('-') => return matching(vec![String::from("--path")]),
+        None => {
+            return matching(
+                commands()
+                    .map(|command| String::from(command.name))
+                    .collect(),
+            )
+        }
+    };
+    match typed.last().map(|word| word.as_str()) {
+        Some("--to") => {
+            return matching(
+                [
+                    "dnsmasq",
+                    "dnsmasq-host-record",
+                    "unbound",
+                    "bind-zone",
+                    "coredns",
+                    "pihole",
+                ]
+                .iter()
+                .map(|format| String::from(*format))
+                .collect(),
+            )
+        }
+        Some("--from") => {
+            return matching(vec![
+                String::from("dnsmasq"),
+                String::from("dnsmasq-host-record"),
+                String::from("unbound"),
+            ])
+        }
+        Some("--validation") => {
+            return matching(
+                ["strict", "resolver", "permissive"]
+                    .iter()
+                    .map(|mode| String::from(*mode))
+                    .collect(),
+            )
+        }
+        // Values such as paths and networks are left to the shell.
+        Some(option) if command.options.contains(&option) && !is_switch(option) => {
+            return Vec::new()
+        }
+        _ => {}
+    }
+    if current.starts_with('-') {
+        return matching(
+            command
+                .options
+                .iter()
+                .map(|option| String::from(*option))
+                .collect(),
+        );
+    }
+
+    if command.candidates == Candidates::Profiles && !typed.iter().any(|word| word == "use") {
+        return matching(vec![String::from("use"), String::from("list")]);
+    }
+
+    let path = typed
+        .iter()
+        .position(|word| word == "--path")
+        .and_then(|index| typed.get(index + 1))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(HostsFile::PATH));
+    matching(candidates(command.candidates, &path))
+}
+
+fn is_switch(option: &str) -> bool {
+    matches!(
+        option,
+        "--by-ip" | "--stdin" | "--force" | "--merge" | "--check" | "--sort"
+    )
+}
+
+fn candidates(kind: Candidates, path: &PathBuf) -> Vec<String> {
+    if kind == Candidates::Shells {
+        return vec![
+            String::from("bash"),
+            String::from("zsh"),
+            String::from("fish"),
+        ];
+    }
+    if kind == Candidates::Nothing {
+        return Vec::new();
+    }
+    let file = match HostsFile::read_from_file(path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+    if kind == Candidates::Profiles {
+        return match file.profiles() {
+            Ok(profiles) => profiles.into_iter().map(|profile| profile.name).collect(),
+            Err(_) => Vec::new(),
+        };
+    }
+    let (ips, hosts) = match kind {
+        Candidates::Entries | Candidates::DisabledEntries => (true, true),
+        Candidates::Ips => (true, false),
+        _ => (false, true),
+    };
+    let mut candidates: Vec<String> = Vec::new();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut push = |candidate: String| {
+        if seen.insert(candidate.clone()) {
+            candidates.push(candidate);
+        }
+    };
+    for line in file.lines.iter() {
+        let disabled;
+        let entry = match line {
+            ParsedLine::Entry(_) if kind == Candidates::DisabledEntries => continue,
+            ParsedLine::Entry(entry) => entry,
+            line if kind == Candidates::DisabledEntries => match line.disabled_entry() {
+                Some(entry) => {
+                    disabled = entry;
+                    &disabled
+                }
+                None => continue,
+            },
+            _ => continue,
+        };
+        if ips {
+            push(entry.ip.to_string());
+        }
+        if hosts {
+            for host in entry.hosts() {
+                push(host.to_string());
+            }
+        }
+    }
+    candidates
+}
@@ -0,0 +1,322 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+use crate::hostsfile::HostsFile;
+
+const TYPE_A: u16 = 1;
+const TYPE_PTR: u16 = 12;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+
+/// Largest response sent over UDP to a client that did not ask for more
+/// with EDNS. Answers that do not fit are cut and flagged as truncated.
+const MAX_UDP_RESPONSE: usize = 512;
+
+/// Response codes this server answers with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseCode {
+    NoError = 0,
+    FormatError = 1,
+    NameError = 3,
+    Refused = 5,
+}
+
+/// A stub DNS server answering from a hosts file: A and PTR queries come
+/// from its entries, AAAA queries for a known host get an empty answer, and
+/// anything else is refused. The file is read again whenever it changes on
+/// disk, so the answers follow it without a restart.
+pub struct DnsServer {
+    socket: UdpSocket,
+    path: PathBuf,
+    file: HostsFile,
+    version: FileVersion,
+}
+
+/// What tells a changed file apart: its metadata, and the hash of its
+/// content for edits that keep the size and land within the resolution of
+/// the modification time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileVersion {
+    modified: SystemTime,
+    len: u64,
+    inode: u64,
+    hash: u64,
+}
+
+impl DnsServer {
+    /// Answers are not meant to be cached, so a change to the file shows up
+    /// on the very next query.
+    pub const TTL: u32 = 0;
+
+    /// How long after a write the metadata alone is not trusted to show a
+    /// further change, which covers coarse modification times.
+    const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+    pub fn bind<A: ToSocketAddrs>(address: A, path: &Path) -> Result<Self, HostsError> {
+        let path = path.to_path_buf();
+        let (version, bytes) = DnsServer::read_version(&path)?;
+        Ok(DnsServer {
+            socket: UdpSocket::bind(address)?,
+            file: DnsServer::parse(&bytes)?,
+            path,
+            version,
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// Answers queries until receiving fails. Errors that only concern one
+    /// query, such as a reply that cannot be delivered or a file that cannot
+    /// be read again, are passed to `log` and the server carries on.
+    pub fn run(&mut self, mut log: impl FnMut(HostsError)) -> std::io::Result<()> {
+        loop {
+            self.serve_one(&mut log)?;
+        }
+    }
+
+    /// Waits for one query and answers it. Queries too broken to answer at
+    /// all are dropped.
+    pub fn serve_one(&mut self, log: &mut impl FnMut(HostsError)) -> std::io::Result<()> {
+        let mut buffer = [0u8; 4096];
+        let (length, client) = match self.socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            // Windows reports an earlier response that could not be
+            // delivered on the next receive; that is no reason to stop.
+            Err(error) if error.kind() == ErrorKind::ConnectionReset => return Ok(()),
+            Err(error) => return Err(error),
+        };
+        // A file that cannot be read keeps the previous entries in service.
+        if let Err(error) = self.reload() {
+            log(error);
+        }
+        if let Some(response) = DnsServer::respond(&self.file, &buffer[..length]) {
+            if let Err(error) = self.socket.send_to(&response, client) {
+                log(HostsError::from(error));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the file again if it changed since it was last read.
+    fn reload(&mut self) -> Result<(), HostsError> {
+        let metadata = std::fs::metadata(&self.path)?;
+        let unchanged = metadata.modified()? == self.version.modified
+            && metadata.len() == self.version.len
+            && DnsServer::inode_of(&metadata) == self.version.inode;
+        let settled = self
+            .version
+            .modified
+            .elapsed()
+            .is_ok_and(|elapsed| elapsed > DnsServer::RACY_WINDOW);
+        if unchanged && settled {
+            return Ok(());
+        }
+        let (version, bytes) = DnsServer::read_version(&self.path)?;
+        if version != self.version {
+            self.file = DnsServer::parse(&bytes)?;
+            self.version = version;
+        }
+        Ok(())
+    }
+
+    fn read_version(path: &Path) -> Result<(FileVersion, Vec<u8>), HostsError> {
+        let metadata = std::fs::metadata(path)?;
+        let bytes = std::fs::read(path)?;
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        let version = FileVersion {
+            modified: metadata.modified()?,
+            len: metadata.len(),
+            inode: DnsServer::inode_of(&metadata),
+            hash: hasher.finish(),
+        };
+        Ok((version, bytes))
+    }
+
+    fn parse(bytes: &[u8]) -> Result<HostsFile, HostsError> {
+        let (lines, _) = HostsFile::decode(bytes)?;
+        Ok(HostsFile::read_from_lines(lines.into_iter().map(Ok))?)
+    }
+
+    #[cfg(unix)]
+    fn inode_of(metadata: &std::fs::Metadata) -> u64 {
+        std::os::unix::fs::MetadataExt::ino(metadata)
+    }
+
+    #[cfg(not(unix))]
+    fn inode_of(_: &std::fs::Metadata) -> u64 {
+        0
+    }
+
+    /// Builds the response to a raw query, or `None` when there is nothing
+    /// to answer: the message is too short to carry an ID, or is a response.
+    pub fn respond(file: &HostsFile, query: &[u8]) -> Option<Vec<u8>> {
+        if query.len() < 12 || query[2] & 0x80 != 0 {
+            return None;
+        }
+        let opcode = (query[2] >> 3) & 0x0F;
+        let question_count = u16::from_be_bytes([query[4], query[5]]);
+        if opcode != 0 || question_count != 1 {
+            return Some(DnsServer::header(query, ResponseCode::Refused, false));
+        }
+        let (name, question_end) = match DnsServer::read_question(query) {
+            Some(question) => question,
+            None => return Some(DnsServer::header(query, ResponseCode::FormatError, false)),
+        };
+        let record_type = u16::from_be_bytes([query[question_end - 4], query[question_end - 3]]);
+        let class = u16::from_be_bytes([query[question_end - 2], query[question_end - 1]]);
+        if class != CLASS_IN {
+            return Some(DnsServer::header(query, ResponseCode::Refused, true));
+        }
+
+        let (code, answers) = match record_type {
+            TYPE_A => DnsServer::answer_a(file, &name),
+            // Hosts files only map IPv4 addresses, so a known host has no
+            // AAAA records rather than not existing.
+            TYPE_AAAA => (DnsServer::answer_a(file, &name).0, Vec::new()),
+            TYPE_PTR => DnsServer::answer_ptr(file, &name),
+            _ => (ResponseCode::Refused, Vec::new()),
+        };
+
+        let mut response = DnsServer::header(query, code, true);
+        response.extend_from_slice(&query[12..question_end]);
+        let mut answer_count: u16 = 0;
+        for (record_type, data) in answers {
+            if response.len() + 12 + data.len() > MAX_UDP_RESPONSE {
+                response[2] |= 0x02;
+                break;
+            }
+            // The owner name points back at the name in the question.
+            response.extend_from_slice(&[0xC0, 0x0C]);
+            response.extend_from_slice(&record_type.to_be_bytes());
+            response.extend_from_slice(&CLASS_IN.to_be_bytes());
+            response.extend_from_slice(&DnsServer::TTL.to_be_bytes());
+            response.extend_from_slice(&(data.len() as u16).to_be_bytes());
+            response.extend_from_slice(&data);
+            answer_count += 1;
+        }
+        response[6..8].copy_from_slice(&answer_count.to_be_bytes());
+        Some(response)
+    }
+
+    fn answer_a(file: &HostsFile, name: &str) -> (ResponseCode, Vec<(u16, Vec<u8>)>) {
+        let host = match HostsEntry::parse_host(name) {
+            Ok(host) => host,
+            Err(_) => return (ResponseCode::NameError, Vec::new()),
+        };
+        let mut ips: Vec<Ipv4Addr> = Vec::new();
+        for (_, entry) in file.find_by_host(&host) {
+            if !ips.contains(&entry.ip) {
+                ips.push(entry.ip);
+            }
+        }
+        if ips.is_empty() {
+            return (ResponseCode::NameError, Vec::new());
+        }
+        let answers = ips
+            .into_iter()
+            .map(|ip| (TYPE_A, ip.octets().to_vec()))
+            .collect();
+        (ResponseCode::NoError, answers)
+    }
+
+    fn answer_ptr(file: &HostsFile, name: &str) -> (ResponseCode, Vec<(u16, Vec<u8>)>) {
+        let ip = match DnsServer::reverse_ip(name) {
+            Some(ip) => ip,
+            None => return (ResponseCode::NameError, Vec::new()),
+        };
+        let mut hosts: Vec<String> = Vec::new();
+        for (_, entry) in file.find_by_ip(ip) {
+            for host in entry.hosts() {
+                let host = host.to_string();
+                if !hosts.contains(&host) {
+                    hosts.push(host);
+                }
+            }
+        }
+        if hosts.is_empty() {
+            return (ResponseCode::NameError, Vec::new());
+        }
+        let answers = hosts
+            .iter()
+            .map(|host| (TYPE_PTR, DnsServer::encode_name(host)))
+            .collect();
+        (ResponseCode::NoError, answers)
+    }
+
+    /// The IP of a name such as `4.3.2.1.in-addr.arpa`.
+    fn reverse_ip(name: &str) -> Option<Ipv4Addr> {
+        let octets = name.strip_suffix(".in-addr.arpa")?;
+        let mut parsed = [0u8; 4];
+        let mut count = 0;
+        for octet in octets.split('.').rev() {
+            if count == 4 || octet.is_empty() || (octet.len() > 1 && octet.starts_with('0')) {
+                return None;
+            }
+            parsed[count] = octet.parse().ok()?;
+            count += 1;
+        }
+        if count != 4 {
+            return None;
+        }
+        Some(Ipv4Addr::from(parsed))
+    }
+
+    /// Reads the single question of a query and returns its name, lowercase
+    /// and without the trailing dot, and where the question ends.
+    fn read_question(query: &[u8]) -> Option<(String, usize)> {
+        let mut labels: Vec<String> = Vec::new();
+        let mut position = 12;
+        loop {
+            let length = *query.get(position)? as usize;
+            position += 1;
+            if length == 0 {
+                break;
+            }
+            // Compression pointers have no place in a question.
+            if length > 63 {
+                return None;
+            }
+            let label = query.get(position..position + length)?;
+            labels.push(String::from_utf8_lossy(label).to_ascii_lowercase());
+            position += length;
+            if position - 12 > 255 {
+                return None;
+            }
+        }
+        let question_end = position + 4;
+        if query.len() < question_end {
+            return None;
+        }
+        Some((labels.join("."), question_end))
+    }
+
+    fn encode_name(name: &str) -> Vec<u8> {
+        let mut encoded = Vec::with_capacity(name.len() + 2);
+        for label in name.split('.').filter(|label| !label.is_empty()) {
+            encoded.push(label.len() as u8);
+            encoded.extend_from_slice(label.as_bytes());
+        }
+        encoded.push(0);
+        encoded
+    }
+
+    /// A response header for `query` with no records in it. The question is
+    /// only echoed back when `with_question` is set.
+    fn header(query: &[u8], code: ResponseCode, with_question: bool) -> Vec<u8> {
+        let mut header = Vec::with_capacity(MAX_UDP_RESPONSE);
+        header.extend_from_slice(&query[0..2]);
+        // QR and AA set, opcode and RD copied from the query.
+        header.push(0x84 | (query[2] & 0x79));
+        header.push(code as u8);
+        header.extend_from_slice(&[0, with_question as u8, 0, 0, 0, 0, 0, 0]);
+        header
+    }
+}
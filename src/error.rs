@@ -0,0 +1,77 @@
+use std::io::ErrorKind;
+
+/// Everything that can go wrong while reading or changing a hosts file.
+/// Each kind has its own exit code, so scripts can tell them apart:
+///
+/// | code | error                                                   |
+/// |------|---------------------------------------------------------|
+/// | 2    | `Parse`: an argument or input line could not be read    |
+/// | 3    | `Validation`: the input was read but is not acceptable  |
+/// | 4    | `NotFound`: nothing matched the hostname or IP          |
+/// | 5    | `Conflict`: the change clashes with the file as it is   |
+/// | 6    | `PermissionDenied`: the file could not be written       |
+/// | 7    | `Lock`: another process kept the file locked too long   |
+/// | 8    | `Io`: any other error from the file system or network   |
+///
+/// Exit code 1 is left to `fmt --check` finding unformatted lines.
+#[derive(Debug)]
+pub enum HostsError {
+    Io(std::io::Error),
+    PermissionDenied(std::io::Error),
+    Parse(String),
+    Validation(String),
+    Conflict(String),
+    NotFound(String),
+    Lock(String),
+}
+
+impl HostsError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            HostsError::Parse(_) => 2,
+            HostsError::Validation(_) => 3,
+            HostsError::NotFound(_) => 4,
+            HostsError::Conflict(_) => 5,
+            HostsError::PermissionDenied(_) => 6,
+            HostsError::Lock(_) => 7,
+            HostsError::Io(_) => 8,
+        }
+    }
+}
+
+impl std::fmt::Display for HostsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostsError::Io(error) => write!(f, "{}", error),
+            HostsError::PermissionDenied(error) => write!(f, "{}", error),
+            HostsError::Parse(message) => write!(f, "{}", message),
+            HostsError::Validation(message) => write!(f, "{}", message),
+            HostsError::Conflict(message) => write!(f, "{}", message),
+            HostsError::NotFound(message) => write!(f, "{}", message),
+            HostsError::Lock(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for HostsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HostsError::Io(error) => Some(error),
+            HostsError::PermissionDenied(error) => Some(error),
+            HostsError::Parse(_) => None,
+            HostsError::Validation(_) => None,
+            HostsError::Conflict(_) => None,
+            HostsError::NotFound(_) => None,
+            HostsError::Lock(_) => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for HostsError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            ErrorKind::PermissionDenied => HostsError::PermissionDenied(error),
+            _ => HostsError::Io(error),
+        }
+    }
+}
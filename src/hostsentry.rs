@@ -2,57 +2,154 @@ use std::net::Ipv4Addr;
 
 use fqdn::{fqdn, FQDN};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+use crate::error::HostsError;
+use crate::validation::Validation;
+
+/// An IP to hostname mapping, with the aliases that share its line. The
+/// inline comment is kept so it survives a rewrite of the file, but it is
+/// not part of the mapping itself and takes no part in equality or ordering.
+#[derive(Debug, Clone)]
 pub struct HostsEntry {
     pub ip: Ipv4Addr,
     pub host: FQDN,
+    pub aliases: Vec<FQDN>,
+    pub comment: Option<String>,
+}
+
+impl PartialEq for HostsEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.ip == other.ip && self.host == other.host && self.aliases == other.aliases
+    }
 }
 
+impl Eq for HostsEntry {}
+
 impl Ord for HostsEntry {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         self.ip
             .cmp(&other.ip)
             .then(self.host.as_c_str().cmp(other.host.as_c_str()))
+            .then_with(|| {
+                self.aliases
+                    .iter()
+                    .map(|alias| alias.as_c_str())
+                    .cmp(other.aliases.iter().map(|alias| alias.as_c_str()))
+            })
     }
 }
 
 impl PartialOrd for HostsEntry {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match self.ip.partial_cmp(&other.ip) {
-            Some(core::cmp::Ordering::Equal) => {}
-            ord => return ord,
-        }
-        self.host.as_c_str().partial_cmp(other.host.as_c_str())
+        Some(self.cmp(other))
     }
 }
 
 impl HostsEntry {
     pub fn new(ip: Ipv4Addr, host: FQDN) -> Self {
-        HostsEntry { ip, host }
+        HostsEntry {
+            ip,
+            host,
+            aliases: Vec::new(),
+            comment: None,
+        }
     }
 
     pub fn from(ip: (u8, u8, u8, u8), fqdn: &str) -> Self {
         HostsEntry {
             ip: Ipv4Addr::new(ip.0, ip.1, ip.2, ip.3),
             host: fqdn!(fqdn),
+            aliases: Vec::new(),
+            comment: None,
         }
     }
 
-    pub fn parse(ip: &str, host: &str) -> Result<Self, String> {
+    pub fn parse(ip: &str, host: &str) -> Result<Self, HostsError> {
+        HostsEntry::parse_with(ip, host, Validation::default())
+    }
+
+    pub fn parse_with(ip: &str, host: &str, validation: Validation) -> Result<Self, HostsError> {
         let parsed_ip = match ip.parse::<Ipv4Addr>() {
             Ok(ip) => ip,
-            Err(error) => return Err(error.to_string()),
-        };
-        let parsed_host = match host.parse::<FQDN>() {
-            Ok(host) => host,
-            Err(error) => return Err(error.to_string()),
+            Err(error) => return Err(HostsError::Parse(format!("{}: {}", error, ip))),
         };
+        let parsed_host = HostsEntry::parse_host_with(host, validation)?;
         Ok(HostsEntry::new(parsed_ip, parsed_host))
     }
+
+    /// Parses a hostname and checks it against `validation`, saying why it
+    /// is rejected if it is.
+    pub fn parse_host_with(host: &str, validation: Validation) -> Result<FQDN, HostsError> {
+        let parsed_host = HostsEntry::parse_host(host)?;
+        match validation.check(&parsed_host.to_string()) {
+            Ok(()) => Ok(parsed_host),
+            Err(reason) => Err(HostsError::Validation(format!(
+                "invalid hostname {}: {}",
+                host, reason
+            ))),
+        }
+    }
+
+    /// Parses a hostname, converting internationalized names to their
+    /// punycode A-label form (`bücher.test` becomes `xn--bcher-kva.test`).
+    /// ASCII names are parsed as they are, so their case is kept. No
+    /// validation mode applies, so any stored name can be looked up.
+    pub fn parse_host(host: &str) -> Result<FQDN, HostsError> {
+        let invalid = |error: fqdn::Error| HostsError::Parse(format!("{}: {}", error, host));
+        if host.is_ascii() {
+            return host.parse::<FQDN>().map_err(invalid);
+        }
+        match idna::domain_to_ascii(host) {
+            Ok(ascii) => ascii.parse::<FQDN>().map_err(invalid),
+            Err(error) => Err(HostsError::Parse(format!(
+                "invalid internationalized name {}: {}",
+                host, error
+            ))),
+        }
+    }
+
+    /// The Unicode form of a hostname, for display. Names that are not
+    /// valid punycode are returned as they are in the file.
+    pub fn unicode_host(host: &FQDN) -> String {
+        let ascii = host.to_string();
+        if !ascii.contains("xn--") {
+            return ascii;
+        }
+        match idna::domain_to_unicode(&ascii) {
+            (unicode, Ok(())) => unicode,
+            (_, Err(_)) => ascii,
+        }
+    }
+
+    /// The hostname followed by its aliases, in file order.
+    pub fn hosts(&self) -> impl Iterator<Item = &FQDN> {
+        std::iter::once(&self.host).chain(self.aliases.iter())
+    }
+
+    pub fn has_host(&self, host: &FQDN) -> bool {
+        self.hosts().any(|entry_host| entry_host == host)
+    }
+
+    /// Drops `host` from the entry, promoting the first alias when it was
+    /// the main hostname. Returns `false` if nothing is left on the line.
+    pub fn remove_host(&mut self, host: &FQDN) -> bool {
+        self.aliases.retain(|alias| alias != host);
+        if self.host != *host {
+            return true;
+        }
+        if self.aliases.is_empty() {
+            return false;
+        }
+        self.host = self.aliases.remove(0);
+        true
+    }
 }
 
 impl std::fmt::Display for HostsEntry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.ip, self.host)
+        write!(f, "{}", self.ip)?;
+        for host in self.hosts() {
+            write!(f, " {}", host)?;
+        }
+        Ok(())
     }
 }
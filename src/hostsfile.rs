@@ -1,45 +1,57 @@
+use std::collections::HashMap;
+
+use self::encoding::TextFormat;
 use self::parse::ParsedLine;
 use crate::hostsentry::HostsEntry;
 
 pub mod add;
+pub mod compact;
+pub mod disable;
+pub mod elevate;
+pub mod encoding;
+pub mod export;
+pub mod format;
+pub mod import;
+pub mod indexed;
+pub mod journal;
+pub mod lock;
 pub mod parse;
+pub mod profile;
+pub mod query;
 pub mod read;
 pub mod remove;
+pub mod rename;
+pub mod retarget;
+pub mod stream;
+pub mod template;
 pub mod write;
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct HostsFile {
     pub lines: Vec<ParsedLine>,
     pub ip_width: usize,
+    pub text_format: TextFormat,
+    /// The text lines had on disk, keyed by their plain form, for the lines
+    /// whose text differs from it. Lets `render` write unchanged lines back
+    /// as they were.
+    pub originals: HashMap<String, Vec<String>>,
+    /// What the policy had to say about the mappings the last write brought
+    /// in, for names outside the domains it allows.
+    pub warnings: Vec<String>,
 }
 
 impl HostsFile {
+    #[cfg(windows)]
     pub const PATH: &str = r"C:\WINDOWS\System32\drivers\etc\hosts";
+    #[cfg(not(windows))]
+    pub const PATH: &str = "/etc/hosts";
 
     pub fn entries(&self) -> Vec<&HostsEntry> {
-        let mut entries = Vec::new();
-        for line in &self.lines {
-            match line {
-                ParsedLine::Entry(entry) => entries.push(entry),
-                ParsedLine::Comment(_) => {}
-                ParsedLine::Invalid(_) => {}
-                ParsedLine::Empty => {}
-            }
-        }
-        entries
+        self.iter_entries().map(|(_, entry)| entry).collect()
     }
 
     pub fn invalids(&self) -> Vec<&String> {
-        let mut invalids = Vec::new();
-        for line in &self.lines {
-            match line {
-                ParsedLine::Invalid(line) => invalids.push(line),
-                ParsedLine::Entry(_) => {}
-                ParsedLine::Comment(_) => {}
-                ParsedLine::Empty => {}
-            }
-        }
-        invalids
+        self.iter_invalids().map(|(_, line)| line).collect()
     }
 
     pub fn entries_count(&self) -> usize {
@@ -1,6 +1,9 @@
+use super::indexed::IndexedHostsFile;
 use super::parse::ParsedLine;
 use super::HostsFile;
+use crate::error::HostsError;
 use crate::hostsentry::HostsEntry;
+use crate::validation::Validation;
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
 
@@ -16,27 +19,121 @@ impl HostsFile {
         &mut self,
         destination_path: &PathBuf,
         entry: &HostsEntry,
-    ) -> std::io::Result<AddResult> {
+    ) -> Result<AddResult, HostsError> {
+        let before = self.render();
         let action = HostsFile::add_to_lines(&mut self.lines, entry);
-        match action {
-            AddResult::Added => HostsFile::write(self, destination_path)?,
-            AddResult::Updated(_) => HostsFile::write(self, destination_path)?,
-            AddResult::Skipped => {}
-        }
+        self.write_journaled(
+            destination_path,
+            &format!("add {}", entry),
+            &format!("{:?}", action),
+            &before,
+        )?;
         Ok(action)
     }
 
+    /// Adds every entry in turn, as `add_to_file` would one by one, and
+    /// writes the file once. `operation` describes the change in the journal.
+    pub fn add_all_to_file(
+        &mut self,
+        destination_path: &PathBuf,
+        entries: &[HostsEntry],
+        operation: &str,
+    ) -> Result<Vec<AddResult>, HostsError> {
+        let before = self.render();
+        let mut indexed = IndexedHostsFile::from(HostsFile {
+            lines: std::mem::take(&mut self.lines),
+            ip_width: self.ip_width,
+            text_format: std::mem::take(&mut self.text_format),
+            originals: std::mem::take(&mut self.originals),
+            warnings: Vec::new(),
+        });
+        let actions: Vec<AddResult> = entries.iter().map(|entry| indexed.add(entry)).collect();
+        *self = indexed.into_file();
+        let (added, updated, skipped) = HostsFile::count_actions(&actions);
+        self.write_journaled(
+            destination_path,
+            operation,
+            &format!("Added({}) Updated({}) Skipped({})", added, updated, skipped),
+            &before,
+        )?;
+        Ok(actions)
+    }
+
+    /// Reads entries from `ip host...` lines, one entry per hostname so each
+    /// is added on its own. Empty lines and comments are skipped; any other
+    /// line that is not an entry fails the whole read.
+    pub fn entries_from_lines(lines: &[String]) -> Result<Vec<HostsEntry>, HostsError> {
+        HostsFile::entries_from_lines_with(lines, Validation::default())
+    }
+
+    pub fn entries_from_lines_with(
+        lines: &[String],
+        validation: Validation,
+    ) -> Result<Vec<HostsEntry>, HostsError> {
+        let mut entries = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            match HostsFile::try_parse_line(line.trim(), validation) {
+                Ok(ParsedLine::Entry(entry)) => {
+                    for host in entry.hosts() {
+                        entries.push(HostsEntry::new(entry.ip, host.clone()));
+                    }
+                }
+                Ok(ParsedLine::Comment(_)) => {}
+                Ok(ParsedLine::Invalid(_)) => {}
+                Ok(ParsedLine::Empty) => {}
+                Err(HostsError::Validation(reason)) => {
+                    return Err(HostsError::Validation(format!(
+                        "invalid entry on line {}: {}",
+                        index + 1,
+                        reason
+                    )))
+                }
+                Err(error) => {
+                    return Err(HostsError::Parse(format!(
+                        "invalid entry on line {}: {} ({})",
+                        index + 1,
+                        line.trim(),
+                        error
+                    )))
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Counts the entries that were added, updated and skipped.
+    pub fn count_actions(actions: &[AddResult]) -> (usize, usize, usize) {
+        let mut counts = (0, 0, 0);
+        for action in actions {
+            match action {
+                AddResult::Added => counts.0 += 1,
+                AddResult::Updated(_) => counts.1 += 1,
+                AddResult::Skipped => counts.2 += 1,
+            }
+        }
+        counts
+    }
+
     pub fn add_to_lines(lines: &mut Vec<ParsedLine>, entry: &HostsEntry) -> AddResult {
         let mut action = AddResult::Added;
-        for line in lines.iter_mut() {
-            match line {
+        let mut index = 0;
+        while index < lines.len() {
+            match &mut lines[index] {
                 ParsedLine::Entry(line_entry) => {
-                    if line_entry.host == entry.host {
+                    if line_entry.has_host(&entry.host) {
                         if line_entry.ip == entry.ip {
                             action = AddResult::Skipped;
-                        } else {
+                        } else if line_entry.aliases.is_empty() {
                             action = AddResult::Updated(line_entry.ip.to_owned());
                             line_entry.ip = entry.ip;
+                        } else {
+                            // The other hosts on the line keep their IP, so
+                            // the updated one moves to a line of its own.
+                            action = AddResult::Updated(line_entry.ip.to_owned());
+                            line_entry.remove_host(&entry.host);
+                            let split = HostsEntry::new(entry.ip, entry.host.clone());
+                            lines.insert(index + 1, ParsedLine::Entry(split));
+                            index += 1;
                         }
                     }
                 }
@@ -44,6 +141,7 @@ impl HostsFile {
                 ParsedLine::Invalid(_) => {}
                 ParsedLine::Empty => {}
             }
+            index += 1;
         }
         match action {
             AddResult::Added => {
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, HashSet};
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+use fqdn::FQDN;
+
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+
+impl HostsFile {
+    /// The Windows DNS client ignores hostnames past the ninth on a line.
+    pub const ALIAS_LIMIT: usize = 9;
+
+    /// Compacts the file and returns the number of entry lines before and
+    /// after. The file is only written when something changed.
+    pub fn compact_file(
+        &mut self,
+        destination_path: &PathBuf,
+        limit: usize,
+    ) -> Result<(usize, usize), HostsError> {
+        let before = self.render();
+        let before_count = self.entries_count();
+        HostsFile::compact_lines(&mut self.lines, limit);
+        let counts = (before_count, self.entries_count());
+        self.write_journaled(
+            destination_path,
+            &format!("compact {}", limit),
+            &format!("Lines({} > {})", counts.0, counts.1),
+            &before,
+        )?;
+        Ok(counts)
+    }
+
+    /// Expands the file and returns the number of entry lines before and
+    /// after. The file is only written when something changed.
+    pub fn expand_file(
+        &mut self,
+        destination_path: &PathBuf,
+    ) -> Result<(usize, usize), HostsError> {
+        let before = self.render();
+        let before_count = self.entries_count();
+        HostsFile::expand_lines(&mut self.lines);
+        let counts = (before_count, self.entries_count());
+        self.write_journaled(
+            destination_path,
+            "expand",
+            &format!("Lines({} > {})", counts.0, counts.1),
+            &before,
+        )?;
+        Ok(counts)
+    }
+
+    /// Merges every hostname that shares an IP within a section onto lines
+    /// of at most `limit` hosts, placed where that IP first appears in it.
+    /// Sections are the runs of entries between other lines, as for
+    /// `sort_sections`, so no entry leaves a `# profile` or `# template`
+    /// block. Entries with an inline comment are left alone so the comment
+    /// keeps describing the same host.
+    pub fn compact_lines(lines: &mut Vec<ParsedLine>, limit: usize) {
+        let limit = limit.max(1);
+        let mut first_lines: HashMap<(usize, Ipv4Addr), usize> = HashMap::new();
+        let mut hosts: HashMap<(usize, Ipv4Addr), Vec<FQDN>> = HashMap::new();
+        let mut seen: HashSet<(usize, Ipv4Addr, &FQDN)> = HashSet::new();
+        let mut section = 0;
+        for (index, line) in lines.iter().enumerate() {
+            match line {
+                ParsedLine::Entry(entry) if entry.comment.is_none() => {
+                    first_lines.entry((section, entry.ip)).or_insert(index);
+                    let ip_hosts = hosts.entry((section, entry.ip)).or_default();
+                    for host in entry.hosts() {
+                        if seen.insert((section, entry.ip, host)) {
+                            ip_hosts.push(host.clone());
+                        }
+                    }
+                }
+                ParsedLine::Entry(_) => {}
+                ParsedLine::Comment(_) | ParsedLine::Invalid(_) | ParsedLine::Empty => {
+                    section += 1;
+                }
+            }
+        }
+
+        let mut compacted = Vec::with_capacity(lines.len());
+        let mut section = 0;
+        for (index, line) in lines.drain(..).enumerate() {
+            match line {
+                ParsedLine::Entry(entry) if entry.comment.is_none() => {
+                    let key = (section, entry.ip);
+                    if first_lines[&key] != index {
+                        continue;
+                    }
+                    for chunk in hosts[&key].chunks(limit) {
+                        compacted.push(ParsedLine::Entry(HostsEntry {
+                            ip: entry.ip,
+                            host: chunk[0].clone(),
+                            aliases: chunk[1..].to_vec(),
+                            comment: None,
+                        }));
+                    }
+                }
+                line @ ParsedLine::Entry(_) => compacted.push(line),
+                line => {
+                    section += 1;
+                    compacted.push(line);
+                }
+            }
+        }
+        *lines = compacted;
+    }
+
+    /// Splits every multi-host line into one line per host. The inline
+    /// comment stays with the first host.
+    pub fn expand_lines(lines: &mut Vec<ParsedLine>) {
+        let mut expanded = Vec::with_capacity(lines.len());
+        for line in lines.drain(..) {
+            match line {
+                ParsedLine::Entry(entry) => {
+                    let mut comment = entry.comment.clone();
+                    for host in entry.hosts() {
+                        expanded.push(ParsedLine::Entry(HostsEntry {
+                            ip: entry.ip,
+                            host: host.clone(),
+                            aliases: Vec::new(),
+                            comment: comment.take(),
+                        }));
+                    }
+                }
+                line => expanded.push(line),
+            }
+        }
+        *lines = expanded;
+    }
+}
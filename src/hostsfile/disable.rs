@@ -0,0 +1,125 @@
+use std::path::PathBuf;
+
+use super::parse::ParsedLine;
+use super::query::Selector;
+use super::HostsFile;
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+
+impl HostsFile {
+    pub fn disable_in_file(
+        &mut self,
+        destination_path: &PathBuf,
+        selector: &Selector,
+    ) -> Result<usize, HostsError> {
+        let before = self.render();
+        let disabled = HostsFile::disable_in_lines(&mut self.lines, selector);
+        if disabled == 0 {
+            return Err(HostsError::NotFound(format!(
+                "no enabled entry matching {}",
+                selector
+            )));
+        }
+        self.write_journaled(
+            destination_path,
+            &format!("disable {}", selector),
+            &format!("Disabled({})", disabled),
+            &before,
+        )?;
+        Ok(disabled)
+    }
+
+    pub fn enable_in_file(
+        &mut self,
+        destination_path: &PathBuf,
+        selector: &Selector,
+    ) -> Result<usize, HostsError> {
+        let before = self.render();
+        let enabled = HostsFile::enable_in_lines(&mut self.lines, selector);
+        if enabled == 0 {
+            return Err(HostsError::NotFound(format!(
+                "no disabled entry matching {}",
+                selector
+            )));
+        }
+        self.write_journaled(
+            destination_path,
+            &format!("enable {}", selector),
+            &format!("Enabled({})", enabled),
+            &before,
+        )?;
+        Ok(enabled)
+    }
+
+    /// Comments out the selected entries and returns the number of hosts
+    /// disabled. A host selected out of a line with aliases is split off, so
+    /// the other hosts stay enabled.
+    pub fn disable_in_lines(lines: &mut Vec<ParsedLine>, selector: &Selector) -> usize {
+        let mut disabled = 0;
+        let mut index = 0;
+        while index < lines.len() {
+            if let ParsedLine::Entry(entry) = &mut lines[index] {
+                if selector.matches(entry) {
+                    let (kept, switched) = HostsFile::split_selected(entry, selector);
+                    disabled += switched.hosts().count();
+                    let switched = ParsedLine::disabled(&switched);
+                    match kept {
+                        Some(kept) => {
+                            lines[index] = ParsedLine::Entry(kept);
+                            lines.insert(index + 1, switched);
+                            index += 1;
+                        }
+                        None => lines[index] = switched,
+                    }
+                }
+            }
+            index += 1;
+        }
+        disabled
+    }
+
+    /// Uncomments the selected commented-out entries and returns the number
+    /// of hosts enabled.
+    pub fn enable_in_lines(lines: &mut Vec<ParsedLine>, selector: &Selector) -> usize {
+        let mut enabled = 0;
+        let mut index = 0;
+        while index < lines.len() {
+            if let Some(mut entry) = lines[index].disabled_entry() {
+                if selector.matches(&entry) {
+                    let (kept, switched) = HostsFile::split_selected(&mut entry, selector);
+                    enabled += switched.hosts().count();
+                    let switched = ParsedLine::Entry(switched);
+                    match kept {
+                        Some(kept) => {
+                            lines[index] = ParsedLine::disabled(&kept);
+                            lines.insert(index + 1, switched);
+                            index += 1;
+                        }
+                        None => lines[index] = switched,
+                    }
+                }
+            }
+            index += 1;
+        }
+        enabled
+    }
+
+    /// Splits a matching entry into the part that stays as it is, if any,
+    /// and the part the selector applies to.
+    fn split_selected(
+        entry: &mut HostsEntry,
+        selector: &Selector,
+    ) -> (Option<HostsEntry>, HostsEntry) {
+        match selector {
+            Selector::Host(host) if !entry.aliases.is_empty() => {
+                let switched = HostsEntry::new(entry.ip, host.clone());
+                if entry.remove_host(host) {
+                    (Some(entry.clone()), switched)
+                } else {
+                    (None, switched)
+                }
+            }
+            _ => (None, entry.clone()),
+        }
+    }
+}
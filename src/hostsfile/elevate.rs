@@ -0,0 +1,136 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use super::HostsFile;
+use crate::error::HostsError;
+
+/// A command that runs another command with the rights to write the hosts
+/// file, such as `sudo`, `doas` or `pkexec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Elevation {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl Elevation {
+    /// The environment variable that holds the elevation command, with any
+    /// arguments it needs, e.g. `HOSTS_ELEVATE="sudo -k"`.
+    pub const VARIABLE: &'static str = "HOSTS_ELEVATE";
+
+    pub fn from_env() -> Option<Self> {
+        Elevation::parse(&std::env::var(Elevation::VARIABLE).ok()?)
+    }
+
+    pub fn parse(command: &str) -> Option<Self> {
+        let mut words = command.split_whitespace().map(String::from);
+        Some(Elevation {
+            program: words.next()?,
+            args: words.collect(),
+        })
+    }
+
+    /// Writes `content` to `path` by piping it into `tee` run through the
+    /// elevation command. Only this step runs with raised rights; `tee`
+    /// keeps the owner and mode of the file it overwrites.
+    pub fn write(&self, path: &Path, content: &[u8]) -> Result<(), HostsError> {
+        self.tee(path, content, false)
+    }
+
+    /// Appends `content` to `path` through `tee -a`.
+    pub fn append(&self, path: &Path, content: &[u8]) -> Result<(), HostsError> {
+        self.tee(path, content, true)
+    }
+
+    fn tee(&self, path: &Path, content: &[u8], append: bool) -> Result<(), HostsError> {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args).arg("tee");
+        if append {
+            command.arg("-a");
+        }
+        let mut child = command
+            .arg(path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()?;
+        // A command that gives up early, such as sudo refusing, closes the
+        // pipe before reading it all. Its exit status says what went wrong,
+        // so it is always waited for, and a broken pipe only counts if it
+        // claims success.
+        let written = match child.stdin.take() {
+            Some(mut stdin) => stdin.write_all(content),
+            None => Ok(()),
+        };
+        let status = child.wait()?;
+        if status.success() {
+            return Ok(written?);
+        }
+        Err(HostsError::PermissionDenied(std::io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "{} could not write {} ({})",
+                self.program,
+                path.display(),
+                status
+            ),
+        )))
+    }
+}
+
+impl HostsFile {
+    /// Writes `content` to `path`. When the file may not be written, the
+    /// write is retried through `elevation`, if there is one.
+    pub fn write_bytes(
+        path: impl AsRef<Path>,
+        content: &[u8],
+        elevation: Option<&Elevation>,
+    ) -> Result<(), HostsError> {
+        let path = path.as_ref();
+        let error = match std::fs::write(path, content) {
+            Ok(()) => return Ok(()),
+            Err(error) if error.kind() == ErrorKind::PermissionDenied => error,
+            Err(error) => return Err(HostsError::Io(error)),
+        };
+        match elevation {
+            Some(elevation) => elevation.write(path, content),
+            None => Err(HostsFile::elevation_hint(path, error)),
+        }
+    }
+
+    /// Appends `content` to `path`, creating it if needed, and retries
+    /// through `elevation` like `write_bytes`.
+    pub fn append_bytes(
+        path: impl AsRef<Path>,
+        content: &[u8],
+        elevation: Option<&Elevation>,
+    ) -> Result<(), HostsError> {
+        let path = path.as_ref();
+        let appended = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(content));
+        let error = match appended {
+            Ok(()) => return Ok(()),
+            Err(error) if error.kind() == ErrorKind::PermissionDenied => error,
+            Err(error) => return Err(HostsError::Io(error)),
+        };
+        match elevation {
+            Some(elevation) => elevation.append(path, content),
+            None => Err(HostsFile::elevation_hint(path, error)),
+        }
+    }
+
+    fn elevation_hint(path: &Path, error: std::io::Error) -> HostsError {
+        HostsError::PermissionDenied(std::io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!(
+                "{}: {}, set {} to a command such as sudo to write it anyway",
+                path.display(),
+                error,
+                Elevation::VARIABLE
+            ),
+        ))
+    }
+}
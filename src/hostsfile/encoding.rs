@@ -0,0 +1,195 @@
+use std::io::ErrorKind;
+
+use super::HostsFile;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl Default for LineEnding {
+    #[cfg(windows)]
+    fn default() -> Self {
+        LineEnding::CrLf
+    }
+
+    #[cfg(not(windows))]
+    fn default() -> Self {
+        LineEnding::Lf
+    }
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::CrLf => write!(f, "CRLF"),
+        }
+    }
+}
+
+/// How the file was stored on disk, so a write reproduces it and only the
+/// lines that actually changed show up in a diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: Encoding,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+    pub final_newline: bool,
+    /// 1-based numbers of the lines whose ending differs from
+    /// `line_ending`. Writing the file makes them consistent.
+    pub mixed_line_endings: Vec<usize>,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        TextFormat {
+            encoding: Encoding::default(),
+            bom: false,
+            line_ending: LineEnding::default(),
+            final_newline: true,
+            mixed_line_endings: Vec::new(),
+        }
+    }
+}
+
+impl HostsFile {
+    /// Splits raw file content into lines, detecting the encoding from the
+    /// BOM (or from NUL bytes, for UTF-16 written without one) and the line
+    /// ending from whichever style the file uses most.
+    pub fn decode(bytes: &[u8]) -> std::io::Result<(Vec<String>, TextFormat)> {
+        let (encoding, bom) = HostsFile::detect_encoding(bytes);
+        let content = match (encoding, bom) {
+            (Encoding::Utf8, true) => HostsFile::decode_utf8(&bytes[3..])?,
+            (Encoding::Utf8, false) => HostsFile::decode_utf8(bytes)?,
+            (_, true) => HostsFile::decode_utf16(&bytes[2..], encoding)?,
+            (_, false) => HostsFile::decode_utf16(bytes, encoding)?,
+        };
+
+        let mut pieces: Vec<&str> = content.split('\n').collect();
+        let final_newline = match pieces.last() {
+            Some(&"") => {
+                pieces.pop();
+                true
+            }
+            _ => false,
+        };
+        let mut lines = Vec::with_capacity(pieces.len());
+        let mut crlf_lines = Vec::new();
+        let mut lf_lines = Vec::new();
+        // Every piece ends with a newline, except the last one when the file
+        // has no final newline.
+        let newline_count = if final_newline {
+            pieces.len()
+        } else {
+            pieces.len().saturating_sub(1)
+        };
+        for (index, piece) in pieces.into_iter().enumerate() {
+            let line = if index >= newline_count {
+                piece
+            } else if let Some(line) = piece.strip_suffix('\r') {
+                crlf_lines.push(index + 1);
+                line
+            } else {
+                lf_lines.push(index + 1);
+                piece
+            };
+            lines.push(String::from(line));
+        }
+        let (line_ending, mixed_line_endings) = if crlf_lines.is_empty() && lf_lines.is_empty() {
+            (LineEnding::default(), Vec::new())
+        } else if crlf_lines.len() >= lf_lines.len() {
+            (LineEnding::CrLf, lf_lines)
+        } else {
+            (LineEnding::Lf, crlf_lines)
+        };
+        let format = TextFormat {
+            encoding,
+            bom,
+            line_ending,
+            final_newline,
+            mixed_line_endings,
+        };
+        Ok((lines, format))
+    }
+
+    /// The encoding of content that starts with `bytes`, and whether it has
+    /// a BOM. The first few bytes are enough to tell.
+    pub fn detect_encoding(bytes: &[u8]) -> (Encoding, bool) {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, true),
+            [0xFF, 0xFE, ..] => (Encoding::Utf16Le, true),
+            [0xFE, 0xFF, ..] => (Encoding::Utf16Be, true),
+            [_, 0, ..] => (Encoding::Utf16Le, false),
+            [0, _, ..] => (Encoding::Utf16Be, false),
+            _ => (Encoding::Utf8, false),
+        }
+    }
+
+    /// Joins lines back into file content in the given format.
+    pub fn encode(lines: &[String], format: &TextFormat) -> Vec<u8> {
+        let mut content = lines.join(format.line_ending.as_str());
+        if format.final_newline && !lines.is_empty() {
+            content.push_str(format.line_ending.as_str());
+        }
+        if format.bom {
+            content.insert(0, '\u{feff}');
+        }
+        match format.encoding {
+            Encoding::Utf8 => content.into_bytes(),
+            Encoding::Utf16Le => content
+                .encode_utf16()
+                .flat_map(|unit| unit.to_le_bytes())
+                .collect(),
+            Encoding::Utf16Be => content
+                .encode_utf16()
+                .flat_map(|unit| unit.to_be_bytes())
+                .collect(),
+        }
+    }
+
+    fn decode_utf8(bytes: &[u8]) -> std::io::Result<String> {
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(content) => Ok(content),
+            Err(error) => Err(std::io::Error::new(ErrorKind::InvalidData, error)),
+        }
+    }
+
+    fn decode_utf16(bytes: &[u8], encoding: Encoding) -> std::io::Result<String> {
+        if !bytes.len().is_multiple_of(2) {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                "UTF-16 content with an odd number of bytes",
+            ));
+        }
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| match encoding {
+                Encoding::Utf16Be => u16::from_be_bytes([pair[0], pair[1]]),
+                _ => u16::from_le_bytes([pair[0], pair[1]]),
+            })
+            .collect();
+        match String::from_utf16(&units) {
+            Ok(content) => Ok(content),
+            Err(error) => Err(std::io::Error::new(ErrorKind::InvalidData, error)),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+
+use fqdn::FQDN;
+
+use super::HostsFile;
+
+/// Configuration formats of other resolvers that entries can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolverFormat {
+    /// `address=/host/ip` lines for dnsmasq. These also answer for every
+    /// subdomain of the name.
+    Dnsmasq,
+    /// `host-record=host,ip` lines for dnsmasq, which answer for the name
+    /// only, as the hosts file does, and for its reverse lookup.
+    DnsmasqHostRecord,
+    /// A `server:` clause of `local-data` and `local-data-ptr` records.
+    Unbound,
+    /// A and PTR records with absolute names, to include in a zone file.
+    BindZone,
+    /// A `hosts` plugin block for a Corefile.
+    CoreDns,
+    /// Pi-hole local DNS records, as in its `custom.list`.
+    PiHole,
+}
+
+impl std::str::FromStr for ResolverFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "dnsmasq" => Ok(ResolverFormat::Dnsmasq),
+            "dnsmasq-host-record" => Ok(ResolverFormat::DnsmasqHostRecord),
+            "unbound" => Ok(ResolverFormat::Unbound),
+            "bind-zone" => Ok(ResolverFormat::BindZone),
+            "coredns" => Ok(ResolverFormat::CoreDns),
+            "pihole" => Ok(ResolverFormat::PiHole),
+            _ => Err(format!(
+                "unknown format {}, expected dnsmasq, dnsmasq-host-record, unbound, bind-zone, coredns or pihole",
+                value
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for ResolverFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolverFormat::Dnsmasq => write!(f, "dnsmasq"),
+            ResolverFormat::DnsmasqHostRecord => write!(f, "dnsmasq-host-record"),
+            ResolverFormat::Unbound => write!(f, "unbound"),
+            ResolverFormat::BindZone => write!(f, "bind-zone"),
+            ResolverFormat::CoreDns => write!(f, "coredns"),
+            ResolverFormat::PiHole => write!(f, "pihole"),
+        }
+    }
+}
+
+impl HostsFile {
+    /// TTL of the records in formats that carry one.
+    pub const EXPORT_TTL: u32 = 3600;
+
+    /// Renders the entries as configuration lines for another resolver.
+    /// Disabled entries and invalid lines are left out, and a hostname that
+    /// is mapped to the same IP more than once is only exported once.
+    pub fn export(&self, format: ResolverFormat) -> Vec<String> {
+        let mappings = self.mappings();
+        let mut lines = Vec::new();
+        match format {
+            ResolverFormat::Dnsmasq => {
+                for (ip, host) in &mappings {
+                    lines.push(format!("address=/{}/{}", host, ip));
+                }
+            }
+            ResolverFormat::DnsmasqHostRecord => {
+                for (ip, host) in &mappings {
+                    lines.push(format!("host-record={},{}", host, ip));
+                }
+            }
+            ResolverFormat::Unbound => {
+                lines.push(String::from("server:"));
+                for (ip, host) in &mappings {
+                    lines.push(format!(
+                        "    local-data: \"{}. {} IN A {}\"",
+                        host,
+                        HostsFile::EXPORT_TTL,
+                        ip
+                    ));
+                }
+                for (ip, host) in HostsFile::reverse_mappings(&mappings) {
+                    lines.push(format!(
+                        "    local-data-ptr: \"{} {} {}.\"",
+                        ip,
+                        HostsFile::EXPORT_TTL,
+                        host
+                    ));
+                }
+            }
+            ResolverFormat::BindZone => {
+                lines.push(format!("$TTL {}", HostsFile::EXPORT_TTL));
+                for (ip, host) in &mappings {
+                    lines.push(format!("{}. IN A {}", host, ip));
+                }
+                for (ip, host) in HostsFile::reverse_mappings(&mappings) {
+                    let [a, b, c, d] = ip.octets();
+                    lines.push(format!(
+                        "{}.{}.{}.{}.in-addr.arpa. IN PTR {}.",
+                        d, c, b, a, host
+                    ));
+                }
+            }
+            ResolverFormat::CoreDns => {
+                lines.push(String::from("hosts {"));
+                lines.push(format!("    ttl {}", HostsFile::EXPORT_TTL));
+                for (ip, host) in &mappings {
+                    lines.push(format!("    {} {}", ip, host));
+                }
+                lines.push(String::from("    fallthrough"));
+                lines.push(String::from("}"));
+            }
+            ResolverFormat::PiHole => {
+                for (ip, host) in &mappings {
+                    lines.push(format!("{} {}", ip, host));
+                }
+            }
+        }
+        lines
+    }
+
+    /// Every IP and hostname pair of the entries, in file order.
+    fn mappings(&self) -> Vec<(Ipv4Addr, &FQDN)> {
+        let mut seen = HashSet::new();
+        let mut mappings = Vec::new();
+        for (_, entry) in self.iter_entries() {
+            for host in entry.hosts() {
+                if seen.insert((entry.ip, host)) {
+                    mappings.push((entry.ip, host));
+                }
+            }
+        }
+        mappings
+    }
+
+    /// The first hostname of every IP, which is the one a reverse lookup
+    /// of the IP gives.
+    fn reverse_mappings<'a>(mappings: &[(Ipv4Addr, &'a FQDN)]) -> Vec<(Ipv4Addr, &'a FQDN)> {
+        let mut seen = HashSet::new();
+        mappings
+            .iter()
+            .filter(|(ip, _)| seen.insert(*ip))
+            .copied()
+            .collect()
+    }
+}
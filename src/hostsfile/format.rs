@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+use super::encoding::TextFormat;
+use super::journal::JournalRecord;
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::audit::{Audit, AuditRecord};
+use crate::error::HostsError;
+use crate::policy::Policy;
+
+impl HostsFile {
+    /// Width of an IP in dotted notation, counted from its octets rather
+    /// than by formatting it, since this runs for every entry of the file.
+    pub fn ip_width(ip: &Ipv4Addr) -> usize {
+        ip.octets()
+            .iter()
+            .map(|octet| match octet {
+                0..=9 => 1,
+                10..=99 => 2,
+                _ => 3,
+            })
+            .sum::<usize>()
+            + 3
+    }
+
+    pub fn ip_width_of(lines: &[ParsedLine]) -> usize {
+        let mut ip_width = 0;
+        for line in lines {
+            match line {
+                ParsedLine::Entry(entry) => {
+                    let entry_ip_width = HostsFile::ip_width(&entry.ip);
+                    if entry_ip_width > ip_width {
+                        ip_width = entry_ip_width;
+                    }
+                }
+                ParsedLine::Comment(_) => {}
+                ParsedLine::Invalid(_) => {}
+                ParsedLine::Empty => {}
+            }
+        }
+        ip_width
+    }
+
+    /// Canonical form of a line: the IP padded to `ip_width`, single spaces
+    /// between columns and no trailing whitespace. Invalid lines are kept
+    /// as they are, since there is no way to tell what they were meant to be.
+    pub fn format_line(line: &ParsedLine, ip_width: usize) -> String {
+        let mut formatted = String::new();
+        HostsFile::format_line_into(&mut formatted, line, ip_width);
+        formatted
+    }
+
+    /// Like `format_line`, but replaces the content of `buffer`, so a loop
+    /// over every line of a file can reuse one allocation.
+    pub fn format_line_into(buffer: &mut String, line: &ParsedLine, ip_width: usize) {
+        buffer.clear();
+        match line {
+            ParsedLine::Entry(entry) => {
+                let _ = write!(buffer, "{:width$}", entry.ip, width = ip_width);
+                for host in entry.hosts() {
+                    // Writing into the buffer skips a temporary String per host.
+                    let _ = write!(buffer, " {}", host);
+                }
+                if let Some(comment) = &entry.comment {
+                    buffer.push(' ');
+                    buffer.push_str(comment);
+                }
+            }
+            ParsedLine::Comment(comment) => buffer.push_str(comment.trim_end()),
+            ParsedLine::Invalid(line) => buffer.push_str(line),
+            ParsedLine::Empty => {}
+        }
+    }
+
+    /// The lines as they are written back. Lines read from the file keep
+    /// their text, so a change only touches the lines it changed; new and
+    /// changed lines are written in their plain form, unaligned.
+    pub fn render(&self) -> Vec<String> {
+        let mut used: HashMap<&str, usize> = HashMap::new();
+        let mut plain = String::new();
+        self.lines
+            .iter()
+            .map(|line| {
+                HostsFile::format_line_into(&mut plain, line, 0);
+                let (key, originals) = match self.originals.get_key_value(plain.as_str()) {
+                    Some(original) => original,
+                    None => return plain.clone(),
+                };
+                let count = used.entry(key.as_str()).or_default();
+                *count += 1;
+                match originals.get(*count - 1) {
+                    Some(original) => original.clone(),
+                    None => plain.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// The lines in canonical form, with every IP padded to the widest one.
+    pub fn render_formatted(&self) -> Vec<String> {
+        let ip_width = HostsFile::ip_width_of(&self.lines);
+        self.lines
+            .iter()
+            .map(|line| HostsFile::format_line(line, ip_width))
+            .collect()
+    }
+
+    /// Sorts each run of consecutive entries, so comments, empty lines and
+    /// invalid lines keep separating the same sections.
+    pub fn sort_sections(lines: &mut [ParsedLine]) {
+        let mut start = 0;
+        while start < lines.len() {
+            let mut end = start;
+            while end < lines.len() && matches!(lines[end], ParsedLine::Entry(_)) {
+                end += 1;
+            }
+            lines[start..end].sort_by(|a, b| match (a, b) {
+                (ParsedLine::Entry(a), ParsedLine::Entry(b)) => a.cmp(b),
+                _ => std::cmp::Ordering::Equal,
+            });
+            start = end + 1;
+        }
+    }
+
+    /// Returns the 1-based numbers of the lines that are not in canonical
+    /// form, without touching the file.
+    pub fn check_format(path: &PathBuf, sort: bool) -> Result<Vec<usize>, HostsError> {
+        let (original, text_format) = HostsFile::read_text_from_file(path)?;
+        let (_, unformatted) = HostsFile::format_lines(&original, &text_format, sort)?;
+        Ok(unformatted)
+    }
+
+    /// Rewrites the file in canonical form and returns the 1-based numbers
+    /// of the lines that changed. The change is audited but not journaled.
+    pub fn format_file(path: &PathBuf, sort: bool) -> Result<Vec<usize>, HostsError> {
+        let _lock = HostsFile::lock(path)?;
+        let (original, text_format) = HostsFile::read_text_from_file(path)?;
+        let (formatted, unformatted) = HostsFile::format_lines(&original, &text_format, sort)?;
+        if !unformatted.is_empty() {
+            Policy::read_for(path)?.review(&original, &formatted)?;
+            let record = JournalRecord::new("fmt", "", &original, &formatted);
+            HostsFile::write_snapshot(path, formatted, &text_format)?;
+            Audit::append(path, &AuditRecord::new("fmt", &record))?;
+        }
+        Ok(unformatted)
+    }
+
+    /// Returns the lines in canonical form and the 1-based numbers of the
+    /// lines that differ from it.
+    fn format_lines(
+        original: &[String],
+        text_format: &TextFormat,
+        sort: bool,
+    ) -> Result<(Vec<String>, Vec<usize>), HostsError> {
+        let mut file = HostsFile::read_from_lines(original.iter().cloned().map(Ok))?;
+        if sort {
+            HostsFile::sort_sections(&mut file.lines);
+        }
+        let formatted = file.render_formatted();
+        let mut unformatted: Vec<usize> = original
+            .iter()
+            .zip(formatted.iter())
+            .enumerate()
+            .filter(|(_, (original, formatted))| original != formatted)
+            .map(|(index, _)| index + 1)
+            .collect();
+        // A line with the other line ending is unformatted too, even if its
+        // content is fine.
+        unformatted.extend(text_format.mixed_line_endings.iter());
+        unformatted.sort_unstable();
+        unformatted.dedup();
+        Ok((formatted, unformatted))
+    }
+}
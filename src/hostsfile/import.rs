@@ -0,0 +1,164 @@
+use std::net::{IpAddr, Ipv4Addr};
+
+use super::export::ResolverFormat;
+use super::HostsFile;
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+use crate::validation::Validation;
+
+/// The entries read from a resolver configuration, the 1-based numbers of
+/// the lines that could not be imported and of those imported in part.
+pub type ImportedConfig = (Vec<HostsEntry>, Vec<usize>, Vec<usize>);
+
+impl HostsFile {
+    /// Reads the IPv4 host overrides out of another resolver's configuration
+    /// and returns them as entries, along with the 1-based numbers of the
+    /// override lines that could not be turned into entries, such as those
+    /// for IPv6 addresses, and of those that were only imported in part:
+    /// dnsmasq `address=` lines also answer for every subdomain of their
+    /// names, which a hosts entry cannot. Other directives are ignored.
+    pub fn parse_resolver_config(
+        format: ResolverFormat,
+        lines: &[String],
+    ) -> Result<ImportedConfig, HostsError> {
+        HostsFile::parse_resolver_config_with(format, lines, Validation::default())
+    }
+
+    /// Like `parse_resolver_config`, but checks the hostnames as `add` would
+    /// with `validation`; a hostname that fails fails the whole import.
+    pub fn parse_resolver_config_with(
+        format: ResolverFormat,
+        lines: &[String],
+        validation: Validation,
+    ) -> Result<ImportedConfig, HostsError> {
+        let parse_line = match format {
+            ResolverFormat::Dnsmasq | ResolverFormat::DnsmasqHostRecord => {
+                HostsFile::parse_dnsmasq_line
+            }
+            ResolverFormat::Unbound => HostsFile::parse_unbound_line,
+            format => {
+                return Err(HostsError::Parse(format!(
+                    "importing from {} is not supported",
+                    format
+                )))
+            }
+        };
+        let mut entries = Vec::new();
+        let mut skipped = Vec::new();
+        let mut partial = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            match parse_line(line.trim(), validation) {
+                Some(Ok(mut line_entries)) => {
+                    if HostsFile::matches_subdomains(format, line.trim()) {
+                        partial.push(index + 1);
+                    }
+                    entries.append(&mut line_entries);
+                }
+                Some(Err(HostsError::Validation(reason))) => {
+                    return Err(HostsError::Validation(format!(
+                        "invalid entry on line {}: {}",
+                        index + 1,
+                        reason
+                    )))
+                }
+                Some(Err(_)) => skipped.push(index + 1),
+                None => {}
+            }
+        }
+        Ok((entries, skipped, partial))
+    }
+
+    /// Whether a `format` line also answers for the subdomains of its names.
+    fn matches_subdomains(format: ResolverFormat, line: &str) -> bool {
+        matches!(
+            format,
+            ResolverFormat::Dnsmasq | ResolverFormat::DnsmasqHostRecord
+        ) && line
+            .split_once('=')
+            .is_some_and(|(directive, _)| directive.trim() == "address")
+    }
+
+    /// `address=/host/.../ip` and `host-record=host,...,ip[,ipv6][,ttl]`.
+    fn parse_dnsmasq_line(
+        line: &str,
+        validation: Validation,
+    ) -> Option<Result<Vec<HostsEntry>, HostsError>> {
+        let (directive, value) = line.split_once('=')?;
+        match directive.trim() {
+            "address" => {
+                let fields: Vec<&str> = value.trim().trim_start_matches('/').split('/').collect();
+                let (ip, hosts) = fields.split_last()?;
+                Some(HostsFile::entries_for(ip, hosts, validation))
+            }
+            "host-record" => {
+                let mut ip = None;
+                let mut hosts = Vec::new();
+                for field in value.split(',').map(|field| field.trim()) {
+                    match field.parse::<IpAddr>() {
+                        Ok(IpAddr::V4(field_ip)) => ip = Some(field_ip),
+                        Ok(IpAddr::V6(_)) => {}
+                        Err(_) if field.chars().all(|c| c.is_ascii_digit()) => {}
+                        Err(_) => hosts.push(field),
+                    }
+                }
+                match ip {
+                    Some(ip) => Some(HostsFile::entries_for(&ip.to_string(), &hosts, validation)),
+                    None => Some(Err(HostsError::Parse(String::from("no IPv4 address")))),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// `local-data: "host [ttl] [class] A ip"`. Other record types are
+    /// skipped; `local-data-ptr` lines follow from the A records anyway.
+    fn parse_unbound_line(
+        line: &str,
+        validation: Validation,
+    ) -> Option<Result<Vec<HostsEntry>, HostsError>> {
+        let value = line.strip_prefix("local-data:")?.trim();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value,
+        };
+        let fields: Vec<&str> = value.split_whitespace().collect();
+        let (host, fields) = fields.split_first()?;
+        let fields: Vec<&str> = fields
+            .iter()
+            .copied()
+            .filter(|field| {
+                !field.chars().all(|c| c.is_ascii_digit()) && !field.eq_ignore_ascii_case("IN")
+            })
+            .collect();
+        match fields.as_slice() {
+            [record_type, ip] if record_type.eq_ignore_ascii_case("A") => {
+                Some(HostsFile::entries_for(ip, &[host], validation))
+            }
+            _ => Some(Err(HostsError::Parse(format!(
+                "unsupported record {}",
+                value
+            )))),
+        }
+    }
+
+    fn entries_for(
+        ip: &str,
+        hosts: &[&str],
+        validation: Validation,
+    ) -> Result<Vec<HostsEntry>, HostsError> {
+        let ip = match ip.trim().parse::<Ipv4Addr>() {
+            Ok(ip) => ip,
+            Err(error) => return Err(HostsError::Parse(format!("{}: {}", error, ip))),
+        };
+        if hosts.is_empty() {
+            return Err(HostsError::Parse(String::from("no hostname")));
+        }
+        hosts
+            .iter()
+            .map(|host| {
+                HostsEntry::parse_host_with(host.trim().trim_end_matches('.'), validation)
+                    .map(|host| HostsEntry::new(ip, host))
+            })
+            .collect()
+    }
+}
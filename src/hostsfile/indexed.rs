@@ -0,0 +1,216 @@
+use std::collections::{BTreeSet, HashMap};
+use std::net::Ipv4Addr;
+
+use fqdn::FQDN;
+
+use super::add::AddResult;
+use super::encoding::TextFormat;
+use super::parse::ParsedLine;
+use super::remove::RemoveResult;
+use super::HostsFile;
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+
+/// A line's place in an `IndexedHostsFile`: the original line it belongs to
+/// and its offset among the lines split off after it.
+type Position = (usize, usize);
+
+/// A `HostsFile` that keeps indexes from hostname and IP to the lines that
+/// hold them, so adding or removing is a lookup instead of a scan of every
+/// line. Meant for bulk changes; the results and the resulting lines are the
+/// same as with `add_to_lines` and `remove_from_lines`.
+///
+/// Lines are never shifted while the index is in use: removed lines become
+/// gaps and lines split off an existing line are grouped with it, which
+/// keeps every stored position valid. `into_file` puts them back in order.
+#[derive(Debug, Default)]
+pub struct IndexedHostsFile {
+    groups: Vec<Vec<Option<ParsedLine>>>,
+    hosts: HashMap<FQDN, BTreeSet<Position>>,
+    ips: HashMap<Ipv4Addr, BTreeSet<Position>>,
+    text_format: TextFormat,
+    originals: HashMap<String, Vec<String>>,
+}
+
+impl From<HostsFile> for IndexedHostsFile {
+    fn from(file: HostsFile) -> Self {
+        let mut indexed = IndexedHostsFile {
+            text_format: file.text_format,
+            originals: file.originals,
+            ..IndexedHostsFile::default()
+        };
+        for line in file.lines {
+            let position = (indexed.groups.len(), 0);
+            if let ParsedLine::Entry(entry) = &line {
+                indexed.index(position, entry);
+            }
+            indexed.groups.push(vec![Some(line)]);
+        }
+        indexed
+    }
+}
+
+impl IndexedHostsFile {
+    pub fn into_file(self) -> HostsFile {
+        let lines: Vec<ParsedLine> = self.groups.into_iter().flatten().flatten().collect();
+        let ip_width = HostsFile::ip_width_of(&lines);
+        HostsFile {
+            lines,
+            ip_width,
+            text_format: self.text_format,
+            originals: self.originals,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn find_by_host(&self, host: &FQDN) -> Vec<&HostsEntry> {
+        self.entries_at(self.hosts.get(host))
+    }
+
+    pub fn find_by_ip(&self, ip: &Ipv4Addr) -> Vec<&HostsEntry> {
+        self.entries_at(self.ips.get(ip))
+    }
+
+    pub fn add(&mut self, entry: &HostsEntry) -> AddResult {
+        // Going backwards keeps the positions still to visit valid when a
+        // line is split, and the last line in file order decides the result
+        // as it does in add_to_lines.
+        let mut action = None;
+        for position in self
+            .positions(self.hosts.get(&entry.host))
+            .into_iter()
+            .rev()
+        {
+            if let Some(line_action) = self.update(position, entry) {
+                action.get_or_insert(line_action);
+            }
+        }
+        match action {
+            Some(action) => action,
+            None => {
+                let position = (self.groups.len(), 0);
+                self.index(position, entry);
+                self.groups
+                    .push(vec![Some(ParsedLine::Entry(entry.clone()))]);
+                AddResult::Added
+            }
+        }
+    }
+
+    fn update(&mut self, position: Position, entry: &HostsEntry) -> Option<AddResult> {
+        let line_entry = self.entry_at(position)?.clone();
+        if line_entry.ip == entry.ip {
+            return Some(AddResult::Skipped);
+        }
+        self.unindex(position, &line_entry);
+        if line_entry.aliases.is_empty() {
+            let mut updated = line_entry.clone();
+            updated.ip = entry.ip;
+            self.set(position, updated);
+        } else {
+            // The other hosts keep their IP and the updated one moves to a
+            // line right after them.
+            let mut kept = line_entry.clone();
+            kept.remove_host(&entry.host);
+            self.set(position, kept);
+            let split_position = (position.0, position.1 + 1);
+            self.groups[position.0].insert(split_position.1, None);
+            self.shift_group(position.0, split_position.1);
+            self.set(
+                split_position,
+                HostsEntry::new(entry.ip, entry.host.clone()),
+            );
+        }
+        Some(AddResult::Updated(line_entry.ip))
+    }
+
+    pub fn remove(&mut self, entry: &str) -> Result<RemoveResult, HostsError> {
+        if let Ok(ip) = entry.parse::<Ipv4Addr>() {
+            let mut removed_count = 0;
+            for position in self.positions(self.ips.get(&ip)) {
+                if let Some(line_entry) = self.entry_at(position).cloned() {
+                    removed_count += line_entry.hosts().count();
+                    self.unindex(position, &line_entry);
+                    self.groups[position.0][position.1] = None;
+                }
+            }
+            return Ok(RemoveResult::RemovedByIp(removed_count));
+        }
+        let host = HostsEntry::parse_host(entry)?;
+        let mut removed_count = 0;
+        for position in self.positions(self.hosts.get(&host)) {
+            if let Some(line_entry) = self.entry_at(position).cloned() {
+                removed_count += 1;
+                self.unindex(position, &line_entry);
+                let mut kept = line_entry;
+                if kept.remove_host(&host) {
+                    self.set(position, kept);
+                } else {
+                    self.groups[position.0][position.1] = None;
+                }
+            }
+        }
+        Ok(RemoveResult::RemovedByHost(removed_count))
+    }
+
+    /// Puts an entry at `position` and indexes it. Whatever was there must
+    /// have been unindexed already.
+    fn set(&mut self, position: Position, entry: HostsEntry) {
+        self.index(position, &entry);
+        self.groups[position.0][position.1] = Some(ParsedLine::Entry(entry));
+    }
+
+    fn entry_at(&self, position: Position) -> Option<&HostsEntry> {
+        match &self.groups[position.0][position.1] {
+            Some(ParsedLine::Entry(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    fn entries_at(&self, positions: Option<&BTreeSet<Position>>) -> Vec<&HostsEntry> {
+        self.positions(positions)
+            .into_iter()
+            .filter_map(|position| self.entry_at(position))
+            .collect()
+    }
+
+    /// The positions of an index entry in file order, copied so the index
+    /// can change while they are visited.
+    fn positions(&self, positions: Option<&BTreeSet<Position>>) -> Vec<Position> {
+        match positions {
+            Some(positions) => positions.iter().copied().collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn index(&mut self, position: Position, entry: &HostsEntry) {
+        for host in entry.hosts() {
+            self.hosts.entry(host.clone()).or_default().insert(position);
+        }
+        self.ips.entry(entry.ip).or_default().insert(position);
+    }
+
+    fn unindex(&mut self, position: Position, entry: &HostsEntry) {
+        for host in entry.hosts() {
+            if let Some(positions) = self.hosts.get_mut(host) {
+                positions.remove(&position);
+            }
+        }
+        if let Some(positions) = self.ips.get_mut(&entry.ip) {
+            positions.remove(&position);
+        }
+    }
+
+    /// Moves the positions of a group's lines from `offset` on one place
+    /// down, after a line was inserted there. Groups only hold a line and
+    /// the few lines split off it, so this stays cheap.
+    fn shift_group(&mut self, group: usize, offset: usize) {
+        let lines = self.groups[group].len();
+        for moved in (offset + 1..lines).rev() {
+            if let Some(entry) = self.entry_at((group, moved)).cloned() {
+                self.unindex((group, moved - 1), &entry);
+                self.index((group, moved), &entry);
+            }
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use super::elevate::Elevation;
+use super::HostsFile;
+use crate::audit::{Audit, AuditRecord};
+use crate::error::HostsError;
+use crate::policy::Policy;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalState {
+    Applied,
+    Undone,
+}
+
+impl std::fmt::Display for JournalState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JournalState::Applied => write!(f, "applied"),
+            JournalState::Undone => write!(f, "undone"),
+        }
+    }
+}
+
+/// A single change to the hosts file, stored as the hunk of lines that
+/// differs between the file before and after the change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalRecord {
+    pub operation: String,
+    pub result: String,
+    pub state: JournalState,
+    pub start: usize,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+    pub before_hash: u64,
+    pub after_hash: u64,
+}
+
+/// The changes made to a hosts file, oldest first, in a `.journal` file
+/// next to it. New changes are appended; undo and redo rewrite it.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Journal {
+    pub records: Vec<JournalRecord>,
+}
+
+/// FNV-1a over the lines of a snapshot. Stable across builds, unlike
+/// `DefaultHasher`, so journals stay valid when the binary is upgraded.
+pub fn snapshot_hash(lines: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for line in lines {
+        for byte in line.bytes().chain(std::iter::once(b'\n')) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+impl JournalRecord {
+    pub fn new(operation: &str, result: &str, before: &[String], after: &[String]) -> Self {
+        let prefix = before
+            .iter()
+            .zip(after.iter())
+            .take_while(|(old, new)| old == new)
+            .count();
+        let suffix = before[prefix..]
+            .iter()
+            .rev()
+            .zip(after[prefix..].iter().rev())
+            .take_while(|(old, new)| old == new)
+            .count();
+        JournalRecord {
+            operation: String::from(operation),
+            result: String::from(result),
+            state: JournalState::Applied,
+            start: prefix,
+            before: before[prefix..before.len() - suffix].to_vec(),
+            after: after[prefix..after.len() - suffix].to_vec(),
+            before_hash: snapshot_hash(before),
+            after_hash: snapshot_hash(after),
+        }
+    }
+
+    /// Replaces the `after` hunk with the `before` hunk, if `lines` is
+    /// exactly the snapshot this record produced.
+    pub fn revert(&self, lines: &mut Vec<String>) -> bool {
+        JournalRecord::splice(
+            lines,
+            self.start,
+            &self.after,
+            self.after_hash,
+            &self.before,
+        )
+    }
+
+    /// Replaces the `before` hunk with the `after` hunk, if `lines` is
+    /// exactly the snapshot this record was made from.
+    pub fn reapply(&self, lines: &mut Vec<String>) -> bool {
+        JournalRecord::splice(
+            lines,
+            self.start,
+            &self.before,
+            self.before_hash,
+            &self.after,
+        )
+    }
+
+    fn splice(
+        lines: &mut Vec<String>,
+        start: usize,
+        expected: &[String],
+        expected_hash: u64,
+        replacement: &[String],
+    ) -> bool {
+        if snapshot_hash(lines) != expected_hash {
+            return false;
+        }
+        let end = start + expected.len();
+        if end > lines.len() || lines[start..end] != *expected {
+            return false;
+        }
+        lines.splice(start..end, replacement.iter().cloned());
+        true
+    }
+}
+
+impl Journal {
+    /// The size the journal may grow to before its oldest changes are
+    /// dropped, after which they can no longer be undone.
+    pub const MAX_BYTES: u64 = 8 * 1024 * 1024;
+
+    pub fn path_for(hosts_path: &Path) -> PathBuf {
+        let mut file_name = hosts_path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".journal");
+        hosts_path.with_file_name(file_name)
+    }
+
+    pub fn read_from_file(path: &PathBuf) -> std::io::Result<Self> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Journal::default()),
+            Err(error) => return Err(error),
+        };
+        Journal::read_from_lines(BufReader::new(file).lines())
+    }
+
+    pub fn read_from_lines<T>(lines: T) -> std::io::Result<Self>
+    where
+        T: Iterator<Item = std::io::Result<String>>,
+    {
+        let mut records: Vec<JournalRecord> = Vec::new();
+        for line in lines {
+            let line = line?;
+            if let Some(header) = line.strip_prefix("@@ ") {
+                let record = Journal::parse_header(header)?;
+                // Undone changes are always the newest ones, so a change
+                // after them was made since the undo, and they can no
+                // longer be redone.
+                if record.state == JournalState::Applied {
+                    records.retain(|record| record.state == JournalState::Applied);
+                }
+                records.push(record);
+                continue;
+            }
+            let record = match records.last_mut() {
+                Some(record) => record,
+                None => return Err(Journal::invalid(&line)),
+            };
+            if let Some(operation) = line.strip_prefix("op ") {
+                record.operation = String::from(operation);
+            } else if let Some(result) = line.strip_prefix("result ") {
+                record.result = String::from(result);
+            } else if let Some(before) = line.strip_prefix('-') {
+                record.before.push(String::from(before));
+            } else if let Some(after) = line.strip_prefix('+') {
+                record.after.push(String::from(after));
+            } else {
+                return Err(Journal::invalid(&line));
+            }
+        }
+        Ok(Journal { records })
+    }
+
+    fn parse_header(header: &str) -> std::io::Result<JournalRecord> {
+        let parts: Vec<&str> = header.split(' ').collect();
+        if parts.len() != 4 {
+            return Err(Journal::invalid(header));
+        }
+        let state = match parts[0] {
+            "applied" => JournalState::Applied,
+            "undone" => JournalState::Undone,
+            _ => return Err(Journal::invalid(header)),
+        };
+        let start = parts[1].parse().map_err(|_| Journal::invalid(header))?;
+        let before_hash =
+            u64::from_str_radix(parts[2], 16).map_err(|_| Journal::invalid(header))?;
+        let after_hash = u64::from_str_radix(parts[3], 16).map_err(|_| Journal::invalid(header))?;
+        Ok(JournalRecord {
+            operation: String::new(),
+            result: String::new(),
+            state,
+            start,
+            before: Vec::new(),
+            after: Vec::new(),
+            before_hash,
+            after_hash,
+        })
+    }
+
+    fn invalid(line: &str) -> std::io::Error {
+        std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid journal line: {}", line),
+        )
+    }
+
+    /// Writes the journal next to the hosts file, through the same
+    /// elevation command if it may not be written directly.
+    pub fn write(&self, path: &Path) -> Result<(), HostsError> {
+        let mut content: Vec<u8> = Vec::new();
+        for record in self.records.iter() {
+            Journal::write_record(&mut content, record)?;
+        }
+        HostsFile::write_bytes(path, &content, Elevation::from_env().as_ref())
+    }
+
+    /// Appends a new change to the journal at `path` without reading it.
+    /// Once the journal grows past `MAX_BYTES`, its oldest changes are
+    /// dropped until it is half that size.
+    pub fn append(path: &Path, record: &JournalRecord) -> Result<(), HostsError> {
+        let mut content: Vec<u8> = Vec::new();
+        Journal::write_record(&mut content, record)?;
+        HostsFile::append_bytes(path, &content, Elevation::from_env().as_ref())?;
+        if std::fs::metadata(path)?.len() > Journal::MAX_BYTES {
+            let mut journal = Journal::read_from_file(&path.to_path_buf())?;
+            journal.trim(Journal::MAX_BYTES / 2)?;
+            journal.write(path)?;
+        }
+        Ok(())
+    }
+
+    /// Drops the oldest records until the rest take at most `max_bytes`.
+    /// The newest record is always kept, however large it is.
+    pub fn trim(&mut self, max_bytes: u64) -> std::io::Result<()> {
+        let mut size = 0;
+        let mut kept = 0;
+        for record in self.records.iter().rev() {
+            let mut content: Vec<u8> = Vec::new();
+            Journal::write_record(&mut content, record)?;
+            size += content.len() as u64;
+            if size > max_bytes && kept > 0 {
+                break;
+            }
+            kept += 1;
+        }
+        let dropped = self.records.len() - kept;
+        self.records.drain(..dropped);
+        Ok(())
+    }
+
+    fn write_record(content: &mut Vec<u8>, record: &JournalRecord) -> std::io::Result<()> {
+        writeln!(
+            content,
+            "@@ {} {} {:016x} {:016x}",
+            record.state, record.start, record.before_hash, record.after_hash
+        )?;
+        writeln!(content, "op {}", record.operation)?;
+        writeln!(content, "result {}", record.result)?;
+        for line in record.before.iter() {
+            writeln!(content, "-{}", line)?;
+        }
+        for line in record.after.iter() {
+            writeln!(content, "+{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl HostsFile {
+    fn journal_record(destination_path: &Path, record: JournalRecord) -> Result<(), HostsError> {
+        Audit::append(
+            destination_path,
+            &AuditRecord::new(&record.operation, &record),
+        )?;
+        Journal::append(&Journal::path_for(destination_path), &record)
+    }
+
+    /// Checks the change against the policy, then writes the file and
+    /// journals it, unless the lines render the same as `before`. Every
+    /// change goes through here, so none of them can bring in a denied
+    /// mapping; warnings about unlisted ones are left in `warnings`.
+    /// Returns whether anything was written.
+    ///
+    /// The caller should hold the file's lock from before it read the file,
+    /// as `read_locked` does, so no other change is lost in between.
+    pub fn write_journaled(
+        &mut self,
+        destination_path: &PathBuf,
+        operation: &str,
+        result: &str,
+        before: &[String],
+    ) -> Result<bool, HostsError> {
+        self.warnings.clear();
+        let after = self.render();
+        if after == before {
+            return Ok(false);
+        }
+        let record = JournalRecord::new(operation, result, before, &after);
+        self.warnings =
+            Policy::read_for(destination_path)?.review(&record.before, &record.after)?;
+        self.write(destination_path)?;
+        HostsFile::journal_record(destination_path, record)?;
+        Ok(true)
+    }
+
+    /// Reverts the last `count` applied changes, newest first. Nothing is
+    /// written if the file no longer matches what the journal recorded, or
+    /// if reverting would bring back a mapping the policy denies.
+    pub fn undo(path: &PathBuf, count: usize) -> Result<Vec<JournalRecord>, HostsError> {
+        let _lock = HostsFile::lock(path)?;
+        let journal_path = Journal::path_for(path);
+        let mut journal = Journal::read_from_file(&journal_path)?;
+        let current = HostsFile::read_from_file(path)?;
+        let mut lines = current.render();
+        let mut reverted = Vec::new();
+        for record in journal.records.iter_mut().rev() {
+            if reverted.len() == count {
+                break;
+            }
+            if record.state != JournalState::Applied {
+                continue;
+            }
+            if !record.revert(&mut lines) {
+                return Err(HostsFile::changed_since(record));
+            }
+            record.state = JournalState::Undone;
+            reverted.push(record.clone());
+        }
+        if !reverted.is_empty() {
+            Policy::read_for(path)?.review(&current.render(), &lines)?;
+            HostsFile::write_snapshot(path, lines, &current.text_format)?;
+            journal.write(&journal_path)?;
+            for record in reverted.iter() {
+                let operation = format!("undo {}", record.operation);
+                Audit::append(path, &AuditRecord::new(&operation, record).reversed())?;
+            }
+        }
+        Ok(reverted)
+    }
+
+    /// Re-applies the last `count` undone changes, oldest first. Like a
+    /// new change, they are checked against the policy first.
+    pub fn redo(path: &PathBuf, count: usize) -> Result<Vec<JournalRecord>, HostsError> {
+        let _lock = HostsFile::lock(path)?;
+        let journal_path = Journal::path_for(path);
+        let mut journal = Journal::read_from_file(&journal_path)?;
+        let current = HostsFile::read_from_file(path)?;
+        let mut lines = current.render();
+        let mut reapplied = Vec::new();
+        for record in journal.records.iter_mut() {
+            if reapplied.len() == count {
+                break;
+            }
+            if record.state != JournalState::Undone {
+                continue;
+            }
+            if !record.reapply(&mut lines) {
+                return Err(HostsFile::changed_since(record));
+            }
+            record.state = JournalState::Applied;
+            reapplied.push(record.clone());
+        }
+        if !reapplied.is_empty() {
+            Policy::read_for(path)?.review(&current.render(), &lines)?;
+            HostsFile::write_snapshot(path, lines, &current.text_format)?;
+            journal.write(&journal_path)?;
+            for record in reapplied.iter() {
+                let operation = format!("redo {}", record.operation);
+                Audit::append(path, &AuditRecord::new(&operation, record))?;
+            }
+        }
+        Ok(reapplied)
+    }
+
+    pub fn history(path: &Path) -> Result<Vec<JournalRecord>, HostsError> {
+        Ok(Journal::read_from_file(&Journal::path_for(path))?.records)
+    }
+
+    fn changed_since(record: &JournalRecord) -> HostsError {
+        HostsError::Conflict(format!(
+            "hosts file has changed since \"{}\", refusing to continue",
+            record.operation
+        ))
+    }
+}
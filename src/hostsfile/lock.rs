@@ -0,0 +1,87 @@
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::HostsFile;
+use crate::error::HostsError;
+use crate::validation::Validation;
+
+/// An exclusive lock on a hosts file. A change takes it before it reads the
+/// file and holds it until the file, its journal and its audit log are
+/// written, so changes made at the same time by several processes are
+/// applied one after the other instead of overwriting each other. Only
+/// processes that take the lock wait for it: a text editor saving the file
+/// does not. It is released when dropped.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: Option<File>,
+}
+
+impl HostsFile {
+    /// How long a change waits for another one to finish.
+    pub const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Locks `path`, waiting up to `LOCK_TIMEOUT` for another process that
+    /// holds the lock to release it.
+    pub fn lock(path: &Path) -> Result<FileLock, HostsError> {
+        HostsFile::lock_within(path, HostsFile::LOCK_TIMEOUT)
+    }
+
+    /// Locks `path`, or fails with `HostsError::Lock` if another process
+    /// still holds the lock after `timeout`. A file that does not exist yet
+    /// has nothing to lock.
+    ///
+    /// The lock is advisory and only taken on Unix: Windows locks are
+    /// mandatory and would keep the file from being written at all.
+    pub fn lock_within(path: &Path, timeout: Duration) -> Result<FileLock, HostsError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Ok(FileLock { _file: None })
+            }
+            Err(error) => return Err(HostsError::from(error)),
+        };
+        #[cfg(unix)]
+        {
+            let started = std::time::Instant::now();
+            loop {
+                match file.try_lock() {
+                    Ok(()) => break,
+                    Err(std::fs::TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                        std::thread::sleep(Duration::from_millis(20));
+                    }
+                    Err(std::fs::TryLockError::WouldBlock) => {
+                        return Err(HostsError::Lock(format!(
+                            "{} is still being changed by another process after {} seconds",
+                            path.display(),
+                            timeout.as_secs_f32()
+                        )))
+                    }
+                    Err(std::fs::TryLockError::Error(error)) => {
+                        return Err(HostsError::from(error))
+                    }
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = timeout;
+        Ok(FileLock { _file: Some(file) })
+    }
+
+    /// Locks `path` and reads it, for a change that is written while the
+    /// returned lock is held, so no other change can come between the read
+    /// and the write.
+    pub fn read_locked(path: &PathBuf) -> Result<(FileLock, HostsFile), HostsError> {
+        HostsFile::read_locked_with(path, Validation::default())
+    }
+
+    pub fn read_locked_with(
+        path: &PathBuf,
+        validation: Validation,
+    ) -> Result<(FileLock, HostsFile), HostsError> {
+        let lock = HostsFile::lock(path)?;
+        let file = HostsFile::read_from_file_with(path, validation)?;
+        Ok((lock, file))
+    }
+}
@@ -1,5 +1,7 @@
 use super::HostsFile;
+use crate::error::HostsError;
 use crate::hostsentry::HostsEntry;
+use crate::validation::Validation;
 use fqdn::FQDN;
 use std::net::Ipv4Addr;
 
@@ -14,7 +16,10 @@ pub enum ParsedLine {
 impl std::fmt::Display for ParsedLine {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParsedLine::Entry(entry) => write!(f, "{}", entry),
+            ParsedLine::Entry(entry) => match &entry.comment {
+                Some(comment) => write!(f, "{} {}", entry, comment),
+                None => write!(f, "{}", entry),
+            },
             ParsedLine::Comment(comment) => write!(f, "{}", comment),
             ParsedLine::Invalid(line) => write!(f, "{}", line),
             ParsedLine::Empty => write!(f, ""),
@@ -22,53 +27,88 @@ impl std::fmt::Display for ParsedLine {
     }
 }
 
+impl ParsedLine {
+    /// A commented-out entry, such as `# 1.2.3.4 host.domain.com`, is how
+    /// hosts files switch an entry off without losing it.
+    pub fn disabled(entry: &HostsEntry) -> ParsedLine {
+        ParsedLine::Comment(format!("# {}", ParsedLine::Entry(entry.clone())))
+    }
+
+    /// The entry held by a commented-out entry line, if this is one.
+    pub fn disabled_entry(&self) -> Option<HostsEntry> {
+        match self {
+            ParsedLine::Comment(comment) => {
+                let content = comment.trim_start_matches('#').trim_start();
+                match HostsFile::parse_line(content) {
+                    ParsedLine::Entry(entry) => Some(entry),
+                    ParsedLine::Comment(_) => None,
+                    ParsedLine::Invalid(_) => None,
+                    ParsedLine::Empty => None,
+                }
+            }
+            ParsedLine::Entry(_) => None,
+            ParsedLine::Invalid(_) => None,
+            ParsedLine::Empty => None,
+        }
+    }
+}
+
 impl HostsFile {
     pub fn parse_line(line: &str) -> ParsedLine {
+        HostsFile::parse_line_with(line, Validation::default())
+    }
+
+    pub fn parse_line_with(line: &str, validation: Validation) -> ParsedLine {
+        match HostsFile::try_parse_line(line, validation) {
+            Ok(parsed) => parsed,
+            Err(_) => ParsedLine::Invalid(String::from(line)),
+        }
+    }
+
+    /// Parses a line like `parse_line_with`, but says why a line that is
+    /// not an entry, a comment or empty is invalid.
+    pub fn try_parse_line(line: &str, validation: Validation) -> Result<ParsedLine, HostsError> {
         if line.is_empty() {
-            return ParsedLine::Empty;
+            return Ok(ParsedLine::Empty);
         } else if line.starts_with(' ') {
-            return ParsedLine::Invalid(String::from(line));
+            return Err(HostsError::Parse(String::from("line starts with a space")));
         }
         if line.starts_with('#') {
-            return ParsedLine::Comment(String::from(line));
+            return Ok(ParsedLine::Comment(String::from(line)));
         }
         let mut ip: Option<Ipv4Addr> = None;
-        let mut host: Option<FQDN> = None;
+        let mut hosts: Vec<FQDN> = Vec::new();
+        let mut comment: Option<String> = None;
+        let mut offset = 0;
         for (index, part) in line.split(' ').enumerate() {
+            let part_start = offset;
+            offset += part.len() + 1;
             if index == 0 {
                 match part.parse::<Ipv4Addr>() {
                     Ok(ipv4addr) => ip = Some(ipv4addr),
-                    Err(_) => return ParsedLine::Invalid(String::from(line)),
+                    Err(error) => return Err(HostsError::Parse(format!("{}: {}", error, part))),
                 }
             } else {
                 if part.is_empty() {
                     continue;
                 }
-                match host {
-                    Some(_) => {
-                        if !part.starts_with('#') {
-                            return ParsedLine::Invalid(String::from(line));
-                        }
-                        break;
-                    }
-                    None => {
-                        match part.trim_end().parse::<FQDN>() {
-                            Ok(fqdn) => {
-                                if fqdn.depth() >= 1 {
-                                    host = Some(fqdn)
-                                } else {
-                                    return ParsedLine::Invalid(String::from(line));
-                                }
-                            }
-                            Err(_) => return ParsedLine::Invalid(String::from(line)),
-                        };
-                    }
+                if part.starts_with('#') && !hosts.is_empty() {
+                    comment = Some(String::from(line[part_start..].trim_end()));
+                    break;
                 }
+                hosts.push(HostsEntry::parse_host_with(part.trim_end(), validation)?);
             }
         }
-        match (ip, host) {
-            (Some(ip), Some(host)) => ParsedLine::Entry(HostsEntry { ip, host }),
-            _ => panic!("Guru meditation!"),
+        let mut hosts = hosts.into_iter();
+        match (ip, hosts.next()) {
+            (Some(ip), Some(host)) => Ok(ParsedLine::Entry(HostsEntry {
+                ip,
+                host,
+                aliases: hosts.collect(),
+                comment,
+            })),
+            // An IP without any hostname maps nothing.
+            _ => Err(HostsError::Parse(String::from("IP without a hostname"))),
         }
     }
 }
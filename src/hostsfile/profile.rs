@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::error::HostsError;
+
+/// A named section of entries, between a `# profile <name>` line and a
+/// `# end profile <name>` line, that is switched on and off as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    pub name: String,
+    /// Index of the opening line.
+    pub start: usize,
+    /// Index of the closing line.
+    pub end: usize,
+    /// Number of enabled hosts in the section.
+    pub enabled: usize,
+    /// Number of commented-out hosts in the section.
+    pub disabled: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileState {
+    Active,
+    Inactive,
+    /// Some of the entries are enabled and some are not.
+    Mixed,
+}
+
+impl std::fmt::Display for ProfileState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileState::Active => write!(f, "active"),
+            ProfileState::Inactive => write!(f, "inactive"),
+            ProfileState::Mixed => write!(f, "mixed"),
+        }
+    }
+}
+
+impl Profile {
+    pub fn state(&self) -> ProfileState {
+        match (self.enabled, self.disabled) {
+            (_, 0) if self.enabled > 0 => ProfileState::Active,
+            (0, _) => ProfileState::Inactive,
+            _ => ProfileState::Mixed,
+        }
+    }
+}
+
+enum Marker<'a> {
+    Start(&'a str),
+    End(&'a str),
+}
+
+impl HostsFile {
+    pub fn profiles(&self) -> Result<Vec<Profile>, HostsError> {
+        HostsFile::profiles_in_lines(&self.lines)
+    }
+
+    /// Finds the profile sections. Sections may not nest, and each name may
+    /// only be used once.
+    pub fn profiles_in_lines(lines: &[ParsedLine]) -> Result<Vec<Profile>, HostsError> {
+        let mut profiles: Vec<Profile> = Vec::new();
+        let mut current: Option<Profile> = None;
+        for (index, line) in lines.iter().enumerate() {
+            match (HostsFile::profile_marker(line), &mut current) {
+                (Some(Marker::Start(name)), None) => {
+                    if profiles.iter().any(|profile| profile.name == name) {
+                        return Err(HostsError::Parse(format!(
+                            "profile {} is defined twice, again on line {}",
+                            name,
+                            index + 1
+                        )));
+                    }
+                    current = Some(Profile {
+                        name: String::from(name),
+                        start: index,
+                        end: index,
+                        enabled: 0,
+                        disabled: 0,
+                    });
+                }
+                (Some(Marker::Start(name)), Some(profile)) => {
+                    return Err(HostsError::Parse(format!(
+                        "profile {} starts on line {} before profile {} ends",
+                        name,
+                        index + 1,
+                        profile.name
+                    )))
+                }
+                (Some(Marker::End(name)), Some(profile)) if name == profile.name => {
+                    profile.end = index;
+                    profiles.extend(current.take());
+                }
+                (Some(Marker::End(name)), _) => {
+                    return Err(HostsError::Parse(format!(
+                        "profile {} ends on line {} without starting",
+                        name,
+                        index + 1
+                    )))
+                }
+                (None, Some(profile)) => {
+                    if let ParsedLine::Entry(entry) = line {
+                        profile.enabled += entry.hosts().count();
+                    } else if let Some(entry) = line.disabled_entry() {
+                        profile.disabled += entry.hosts().count();
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        match current {
+            Some(profile) => Err(HostsError::Parse(format!(
+                "profile {} never ends, add \"# end profile {}\"",
+                profile.name, profile.name
+            ))),
+            None => Ok(profiles),
+        }
+    }
+
+    /// Enables the entries of the profile `name` and comments out those of
+    /// every other profile. Returns the number of hosts enabled and disabled.
+    pub fn use_profile_in_file(
+        &mut self,
+        destination_path: &PathBuf,
+        name: &str,
+    ) -> Result<(usize, usize), HostsError> {
+        let before = self.render();
+        let (enabled, disabled) = HostsFile::use_profile_in_lines(&mut self.lines, name)?;
+        self.write_journaled(
+            destination_path,
+            &format!("profile use {}", name),
+            &format!("Switched({}, {})", enabled, disabled),
+            &before,
+        )?;
+        Ok((enabled, disabled))
+    }
+
+    pub fn use_profile_in_lines(
+        lines: &mut [ParsedLine],
+        name: &str,
+    ) -> Result<(usize, usize), HostsError> {
+        let profiles = HostsFile::profiles_in_lines(lines)?;
+        if !profiles.iter().any(|profile| profile.name == name) {
+            return Err(HostsError::NotFound(format!("no profile named {}", name)));
+        }
+        let mut enabled = 0;
+        let mut disabled = 0;
+        for profile in profiles.iter() {
+            for line in lines[profile.start + 1..profile.end].iter_mut() {
+                if profile.name == name {
+                    if let Some(entry) = line.disabled_entry() {
+                        enabled += entry.hosts().count();
+                        *line = ParsedLine::Entry(entry);
+                    }
+                } else if let ParsedLine::Entry(entry) = line {
+                    disabled += entry.hosts().count();
+                    *line = ParsedLine::disabled(entry);
+                }
+            }
+        }
+        Ok((enabled, disabled))
+    }
+
+    /// Reads a `# profile <name>` or `# end profile <name>` line. Names are
+    /// single words, so other comments that happen to start with "profile"
+    /// are not taken for markers.
+    fn profile_marker(line: &ParsedLine) -> Option<Marker<'_>> {
+        let comment = match line {
+            ParsedLine::Comment(comment) => comment.trim_start_matches('#'),
+            _ => return None,
+        };
+        let words: Vec<&str> = comment.split_whitespace().collect();
+        match words.as_slice() {
+            ["profile", name] => Some(Marker::Start(name)),
+            ["end", "profile", name] => Some(Marker::End(name)),
+            _ => None,
+        }
+    }
+}
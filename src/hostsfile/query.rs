@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+
+use fqdn::FQDN;
+
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::cidr::Ipv4Cidr;
+use crate::hostsentry::HostsEntry;
+
+/// What a command applies to: every entry with a hostname, every entry on
+/// an IP, or every entry with an IP inside a network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Selector {
+    Host(FQDN),
+    Ip(Ipv4Addr),
+    Network(Ipv4Cidr),
+}
+
+impl Selector {
+    pub fn matches(&self, entry: &HostsEntry) -> bool {
+        match self {
+            Selector::Host(host) => entry.has_host(host),
+            Selector::Ip(ip) => entry.ip == *ip,
+            Selector::Network(network) => network.contains(&entry.ip),
+        }
+    }
+}
+
+impl std::str::FromStr for Selector {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value.contains('/') {
+            return value.parse::<Ipv4Cidr>().map(Selector::Network);
+        }
+        if let Ok(ip) = value.parse::<Ipv4Addr>() {
+            return Ok(Selector::Ip(ip));
+        }
+        HostsEntry::parse_host(value)
+            .map(Selector::Host)
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl std::fmt::Display for Selector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Selector::Host(host) => write!(f, "{}", host),
+            Selector::Ip(ip) => write!(f, "{}", ip),
+            Selector::Network(network) => write!(f, "{}", network),
+        }
+    }
+}
+
+/// Every hostname that maps to an IP, split by whether the entry mapping it
+/// is enabled or commented out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpHosts {
+    pub ip: Ipv4Addr,
+    pub hosts: Vec<FQDN>,
+    pub disabled: Vec<FQDN>,
+}
+
+impl HostsFile {
+    /// Iterates over the entries together with their 0-based line index.
+    pub fn iter_entries(&self) -> impl Iterator<Item = (usize, &HostsEntry)> + '_ {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| match line {
+                ParsedLine::Entry(entry) => Some((index, entry)),
+                ParsedLine::Comment(_) => None,
+                ParsedLine::Invalid(_) => None,
+                ParsedLine::Empty => None,
+            })
+    }
+
+    /// Iterates over the invalid lines together with their 0-based line index.
+    pub fn iter_invalids(&self) -> impl Iterator<Item = (usize, &String)> + '_ {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(index, line)| match line {
+                ParsedLine::Invalid(line) => Some((index, line)),
+                ParsedLine::Entry(_) => None,
+                ParsedLine::Comment(_) => None,
+                ParsedLine::Empty => None,
+            })
+    }
+
+    /// Entries that have `host` as their hostname or as one of their aliases.
+    pub fn find_by_host<'a>(
+        &'a self,
+        host: &'a FQDN,
+    ) -> impl Iterator<Item = (usize, &'a HostsEntry)> + 'a {
+        self.iter_entries()
+            .filter(move |(_, entry)| entry.has_host(host))
+    }
+
+    pub fn find_by_ip(&self, ip: Ipv4Addr) -> impl Iterator<Item = (usize, &HostsEntry)> + '_ {
+        self.iter_entries().filter(move |(_, entry)| entry.ip == ip)
+    }
+
+    pub fn find_in_network(
+        &self,
+        network: Ipv4Cidr,
+    ) -> impl Iterator<Item = (usize, &HostsEntry)> + '_ {
+        self.iter_entries()
+            .filter(move |(_, entry)| network.contains(&entry.ip))
+    }
+
+    /// Groups the hostnames of every entry by IP, aliases and disabled
+    /// entries included. The groups are sorted by IP, and the hostnames of a
+    /// group by the entries they come from, in the order entries sort in. A
+    /// hostname that is both enabled and disabled for an IP is only listed
+    /// as enabled.
+    pub fn group_by_ip(&self) -> Vec<IpHosts> {
+        let mut entries: Vec<(HostsEntry, bool)> = self
+            .lines
+            .iter()
+            .filter_map(|line| match line {
+                ParsedLine::Entry(entry) => Some((entry.clone(), true)),
+                line => line.disabled_entry().map(|entry| (entry, false)),
+            })
+            .collect();
+        // Enabled entries go before the disabled ones for the same IP, so a
+        // hostname that is both is seen enabled first.
+        entries.sort_by(|(a, a_enabled), (b, b_enabled)| {
+            a.ip.cmp(&b.ip)
+                .then(b_enabled.cmp(a_enabled))
+                .then_with(|| a.cmp(b))
+        });
+
+        let mut groups: Vec<IpHosts> = Vec::new();
+        let mut seen: HashSet<&FQDN> = HashSet::new();
+        for (entry, enabled) in entries.iter() {
+            let group = match groups.last_mut() {
+                Some(group) if group.ip == entry.ip => group,
+                _ => {
+                    seen.clear();
+                    groups.push(IpHosts {
+                        ip: entry.ip,
+                        hosts: Vec::new(),
+                        disabled: Vec::new(),
+                    });
+                    groups.last_mut().unwrap()
+                }
+            };
+            let hosts = if *enabled {
+                &mut group.hosts
+            } else {
+                &mut group.disabled
+            };
+            for host in entry.hosts() {
+                if seen.insert(host) {
+                    hosts.push(host.clone());
+                }
+            }
+        }
+        groups
+    }
+
+    /// Every hostname that points at `ip`, if any does.
+    pub fn hosts_for_ip(&self, ip: Ipv4Addr) -> Option<IpHosts> {
+        self.group_by_ip().into_iter().find(|group| group.ip == ip)
+    }
+
+    pub fn select<'a>(
+        &'a self,
+        selector: &'a Selector,
+    ) -> impl Iterator<Item = (usize, &'a HostsEntry)> + 'a {
+        self.iter_entries()
+            .filter(move |(_, entry)| selector.matches(entry))
+    }
+}
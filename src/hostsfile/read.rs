@@ -1,47 +1,68 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use super::parse::ParsedLine;
+use super::encoding::TextFormat;
 use super::HostsFile;
+use crate::error::HostsError;
+use crate::validation::Validation;
 
 impl HostsFile {
-    pub fn read_from_file(path: &PathBuf) -> std::io::Result<Self> {
-        let file = File::open(path)?;
-        let reader = BufReader::new(file);
-        let read_file = HostsFile::read_from_lines(reader.lines())?;
+    pub fn read_from_file(path: &PathBuf) -> Result<Self, HostsError> {
+        HostsFile::read_from_file_with(path, Validation::default())
+    }
+
+    pub fn read_from_file_with(path: &PathBuf, validation: Validation) -> Result<Self, HostsError> {
+        let (lines, text_format) = HostsFile::read_text_from_file(path)?;
+        let mut read_file = HostsFile::read_from_lines_with(lines.into_iter().map(Ok), validation)?;
+        read_file.text_format = text_format;
         Ok(read_file)
     }
 
+    /// The lines of the file as they are on disk, without their line
+    /// endings, along with the format they were stored in.
+    pub fn read_text_from_file(path: &PathBuf) -> Result<(Vec<String>, TextFormat), HostsError> {
+        let bytes = std::fs::read(path)?;
+        Ok(HostsFile::decode(&bytes)?)
+    }
+
     pub fn read_from_lines<T>(lines: T) -> std::io::Result<HostsFile>
+    where
+        T: Iterator<Item = std::io::Result<String>>,
+    {
+        HostsFile::read_from_lines_with(lines, Validation::default())
+    }
+
+    pub fn read_from_lines_with<T>(lines: T, validation: Validation) -> std::io::Result<HostsFile>
     where
         T: Iterator<Item = std::io::Result<String>>,
     {
         let mut parsed_lines = Vec::new();
-        let mut ip_width = 0;
-        for line in lines {
-            match line {
-                Ok(line) => {
-                    let parsed_line = HostsFile::parse_line(&line);
-                    match &parsed_line {
-                        ParsedLine::Entry(entry) => {
-                            let entry_ip_width = format!("{}", entry.ip).len();
-                            if entry_ip_width > ip_width {
-                                ip_width = entry_ip_width;
-                            }
-                        }
-                        ParsedLine::Comment(_) => {}
-                        ParsedLine::Invalid(_) => {}
-                        ParsedLine::Empty => {}
-                    }
-                    parsed_lines.push(parsed_line);
-                }
-                Err(error) => return Err(error),
+        let mut originals: HashMap<String, Vec<String>> = HashMap::new();
+        let mut plain = String::new();
+        for (index, line) in lines.enumerate() {
+            let line = line?;
+            // A UTF-8 BOM would otherwise end up in the first line.
+            let text = match index {
+                0 => line.trim_start_matches('\u{feff}'),
+                _ => line.as_str(),
+            };
+            let parsed = HostsFile::parse_line_with(text, validation);
+            HostsFile::format_line_into(&mut plain, &parsed, 0);
+            if plain != text {
+                originals
+                    .entry(plain.clone())
+                    .or_default()
+                    .push(String::from(text));
             }
+            parsed_lines.push(parsed);
         }
+        let ip_width = HostsFile::ip_width_of(&parsed_lines);
         Ok(HostsFile {
             lines: parsed_lines,
             ip_width,
+            text_format: TextFormat::default(),
+            originals,
+            warnings: Vec::new(),
         })
     }
 }
@@ -1,14 +1,15 @@
 use super::parse::ParsedLine;
 use super::HostsFile;
-use fqdn::FQDN;
+use crate::cidr::Ipv4Cidr;
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
 use std::net::Ipv4Addr;
 use std::path::PathBuf;
 
 #[derive(Debug)]
 pub enum RemoveResult {
-    RemovedByIp(u8),
-    RemovedByHost(u8),
-    Invalid,
+    RemovedByIp(usize),
+    RemovedByHost(usize),
 }
 
 impl HostsFile {
@@ -16,47 +17,109 @@ impl HostsFile {
         &mut self,
         destination_path: &PathBuf,
         entry: &str,
-    ) -> Result<RemoveResult, std::io::Error> {
-        let action = HostsFile::remove_from_lines(&mut self.lines, entry);
+    ) -> Result<RemoveResult, HostsError> {
+        let before = self.render();
+        let action = HostsFile::remove_from_lines(&mut self.lines, entry)?;
         match action {
-            RemoveResult::RemovedByIp(_) => self.write(destination_path)?,
-            RemoveResult::RemovedByHost(_) => self.write(destination_path)?,
-            RemoveResult::Invalid => {}
+            RemoveResult::RemovedByIp(0) => {
+                return Err(HostsError::NotFound(format!("no entry with ip {}", entry)))
+            }
+            RemoveResult::RemovedByHost(0) => {
+                return Err(HostsError::NotFound(format!(
+                    "no entry with hostname {}",
+                    entry
+                )))
+            }
+            RemoveResult::RemovedByIp(_) => {}
+            RemoveResult::RemovedByHost(_) => {}
         }
+        self.write_journaled(
+            destination_path,
+            &format!("remove {}", entry),
+            &format!("{:?}", action),
+            &before,
+        )?;
         Ok(action)
     }
 
-    pub fn remove_from_lines(lines: &mut Vec<ParsedLine>, entry: &str) -> RemoveResult {
+    pub fn remove_network_from_file(
+        &mut self,
+        destination_path: &PathBuf,
+        network: &Ipv4Cidr,
+    ) -> Result<usize, HostsError> {
+        let before = self.render();
+        let removed = HostsFile::remove_network_from_lines(&mut self.lines, network);
+        if removed == 0 {
+            return Err(HostsError::NotFound(format!("no entry within {}", network)));
+        }
+        self.write_journaled(
+            destination_path,
+            &format!("remove --cidr {}", network),
+            &format!("RemovedByNetwork({})", removed),
+            &before,
+        )?;
+        Ok(removed)
+    }
+
+    /// Removes every entry with an IP inside `network` and returns the
+    /// number of hosts removed.
+    pub fn remove_network_from_lines(lines: &mut Vec<ParsedLine>, network: &Ipv4Cidr) -> usize {
+        let mut removed_count = 0;
+        lines.retain(|line| match line {
+            ParsedLine::Entry(line_entry) => {
+                if !network.contains(&line_entry.ip) {
+                    return true;
+                }
+                removed_count += line_entry.hosts().count();
+                false
+            }
+            ParsedLine::Comment(_) => true,
+            ParsedLine::Invalid(_) => true,
+            ParsedLine::Empty => true,
+        });
+        removed_count
+    }
+
+    pub fn remove_from_lines(
+        lines: &mut Vec<ParsedLine>,
+        entry: &str,
+    ) -> Result<RemoveResult, HostsError> {
         let parsed_ip = entry.parse::<Ipv4Addr>();
-        let parsed_host = entry.parse::<FQDN>();
-        if parsed_ip.is_err() && parsed_host.is_err() {
-            return RemoveResult::Invalid;
+        let parsed_host = HostsEntry::parse_host(entry);
+        if let (Err(_), Err(error)) = (&parsed_ip, &parsed_host) {
+            return Err(HostsError::Parse(error.to_string()));
         }
 
         let mut removed_count = 0;
-        lines.retain(|line| {
-            let retain = match line {
-                ParsedLine::Entry(line_entry) => match parsed_ip {
-                    Ok(ref ip) => *ip != line_entry.ip,
-                    Err(_) => match parsed_host {
-                        Ok(ref host) => *host != line_entry.host,
-                        Err(_) => true,
-                    },
+        lines.retain_mut(|line| match line {
+            ParsedLine::Entry(line_entry) => match parsed_ip {
+                Ok(ref ip) => {
+                    if *ip != line_entry.ip {
+                        return true;
+                    }
+                    removed_count += line_entry.hosts().count();
+                    false
+                }
+                Err(_) => match parsed_host {
+                    Ok(ref host) => {
+                        if !line_entry.has_host(host) {
+                            return true;
+                        }
+                        removed_count += 1;
+                        line_entry.remove_host(host)
+                    }
+                    Err(_) => true,
                 },
-                ParsedLine::Comment(_) => true,
-                ParsedLine::Invalid(_) => true,
-                ParsedLine::Empty => true,
-            };
-            if !retain {
-                removed_count += 1;
-            }
-            retain
+            },
+            ParsedLine::Comment(_) => true,
+            ParsedLine::Invalid(_) => true,
+            ParsedLine::Empty => true,
         });
 
         if parsed_ip.is_ok() {
-            RemoveResult::RemovedByIp(removed_count)
+            Ok(RemoveResult::RemovedByIp(removed_count))
         } else {
-            RemoveResult::RemovedByHost(removed_count)
+            Ok(RemoveResult::RemovedByHost(removed_count))
         }
     }
 }
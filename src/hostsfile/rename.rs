@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+use fqdn::FQDN;
+
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+use crate::validation::Validation;
+
+impl HostsFile {
+    pub fn rename_in_file(
+        &mut self,
+        destination_path: &PathBuf,
+        old_host: &str,
+        new_host: &str,
+        merge: bool,
+    ) -> Result<usize, HostsError> {
+        self.rename_in_file_with(
+            destination_path,
+            old_host,
+            new_host,
+            merge,
+            Validation::default(),
+        )
+    }
+
+    /// Like `rename_in_file`, but checks `new_host` as `add` would with
+    /// `validation`.
+    pub fn rename_in_file_with(
+        &mut self,
+        destination_path: &PathBuf,
+        old_host: &str,
+        new_host: &str,
+        merge: bool,
+        validation: Validation,
+    ) -> Result<usize, HostsError> {
+        let old = HostsEntry::parse_host_with(old_host, Validation::Permissive)?;
+        let new = HostsEntry::parse_host_with(new_host, validation)?;
+        let before = self.render();
+        let renamed_count = HostsFile::rename_in_lines(&mut self.lines, &old, &new, merge)?;
+        let mut operation = format!("rename {} {}", old, new);
+        if merge {
+            operation.push_str(" --merge");
+        }
+        self.write_journaled(
+            destination_path,
+            &operation,
+            &format!("Renamed({})", renamed_count),
+            &before,
+        )?;
+        Ok(renamed_count)
+    }
+
+    /// Replaces `old` with `new` wherever it appears, leaving the IP, the
+    /// other hostnames and the comment of each line as they were. Nothing
+    /// changes if `new` is already in use, unless `merge` is set; a line that
+    /// then holds `new` twice keeps it once, where it came first. A merge
+    /// that would point `new` at an IP it does not point at yet is refused.
+    /// Returns the number of lines that changed.
+    pub fn rename_in_lines(
+        lines: &mut [ParsedLine],
+        old: &FQDN,
+        new: &FQDN,
+        merge: bool,
+    ) -> Result<usize, HostsError> {
+        if old == new {
+            return Err(HostsError::Validation(format!(
+                "{} would be renamed to itself",
+                old
+            )));
+        }
+        let mut old_ips: HashSet<Ipv4Addr> = HashSet::new();
+        let mut new_ips: HashSet<Ipv4Addr> = HashSet::new();
+        for line in lines.iter() {
+            if let ParsedLine::Entry(entry) = line {
+                if entry.has_host(old) {
+                    old_ips.insert(entry.ip);
+                }
+                if entry.has_host(new) {
+                    new_ips.insert(entry.ip);
+                }
+            }
+        }
+        let (found, exists) = (!old_ips.is_empty(), !new_ips.is_empty());
+        if !found {
+            return Err(HostsError::NotFound(format!(
+                "no entry with hostname {}",
+                old
+            )));
+        }
+        if exists && !merge {
+            return Err(HostsError::Conflict(format!(
+                "{} is already in use, pass --merge to merge the two",
+                new
+            )));
+        }
+        if let Some(ip) = old_ips.iter().find(|ip| exists && !new_ips.contains(ip)) {
+            return Err(HostsError::Conflict(format!(
+                "{} points at {}, which {} does not, merging would map it to two IPs",
+                old, ip, new
+            )));
+        }
+
+        let mut renamed_count = 0;
+        for line in lines.iter_mut() {
+            let entry = match line {
+                ParsedLine::Entry(entry) if entry.has_host(old) => entry,
+                _ => continue,
+            };
+            let mut hosts: Vec<FQDN> = Vec::with_capacity(entry.aliases.len() + 1);
+            for host in entry.hosts() {
+                let host = if host == old { new } else { host };
+                if !hosts.contains(host) {
+                    hosts.push(host.clone());
+                }
+            }
+            entry.host = hosts.remove(0);
+            entry.aliases = hosts;
+            renamed_count += 1;
+        }
+        Ok(renamed_count)
+    }
+}
@@ -0,0 +1,62 @@
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+use fqdn::FQDN;
+
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::error::HostsError;
+
+impl HostsFile {
+    /// Moves every entry on `old_ip` to `new_ip` in a single write and
+    /// returns the hostnames that moved. Fails if no entry is on `old_ip`.
+    pub fn retarget_in_file(
+        &mut self,
+        destination_path: &PathBuf,
+        old_ip: Ipv4Addr,
+        new_ip: Ipv4Addr,
+    ) -> Result<Vec<FQDN>, HostsError> {
+        let before = self.render();
+        let hosts = HostsFile::retarget_in_lines(&mut self.lines, old_ip, new_ip);
+        if hosts.is_empty() && old_ip != new_ip {
+            return Err(HostsError::NotFound(format!("no entry with ip {}", old_ip)));
+        }
+        self.write_journaled(
+            destination_path,
+            &format!("retarget {} {}", old_ip, new_ip),
+            &format!("Retargeted({})", hosts.len()),
+            &before,
+        )?;
+        Ok(hosts)
+    }
+
+    /// Changes the IP of every entry on `old_ip`, keeping its hostnames,
+    /// comment and position, and returns the hostnames that moved.
+    pub fn retarget_in_lines(
+        lines: &mut [ParsedLine],
+        old_ip: Ipv4Addr,
+        new_ip: Ipv4Addr,
+    ) -> Vec<FQDN> {
+        let mut hosts: Vec<FQDN> = Vec::new();
+        if old_ip == new_ip {
+            return hosts;
+        }
+        for line in lines.iter_mut() {
+            match line {
+                ParsedLine::Entry(entry) if entry.ip == old_ip => {
+                    entry.ip = new_ip;
+                    for host in entry.hosts() {
+                        if !hosts.contains(host) {
+                            hosts.push(host.clone());
+                        }
+                    }
+                }
+                ParsedLine::Entry(_) => {}
+                ParsedLine::Comment(_) => {}
+                ParsedLine::Invalid(_) => {}
+                ParsedLine::Empty => {}
+            }
+        }
+        hosts
+    }
+}
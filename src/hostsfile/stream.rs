@@ -0,0 +1,132 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines, Read, Write};
+use std::path::PathBuf;
+
+use super::encoding::Encoding;
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::validation::Validation;
+
+/// Parses lines lazily, one at a time, so a file of any size can be
+/// processed without holding all of it in memory.
+pub struct ParsedLines<T> {
+    lines: T,
+    first: bool,
+    validation: Validation,
+}
+
+impl<T> Iterator for ParsedLines<T>
+where
+    T: Iterator<Item = std::io::Result<String>>,
+{
+    type Item = std::io::Result<ParsedLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        // A UTF-8 BOM would otherwise end up in the first line.
+        let first = std::mem::replace(&mut self.first, false);
+        Some(line.map(|line| {
+            if first {
+                HostsFile::parse_line_with(line.trim_start_matches('\u{feff}'), self.validation)
+            } else {
+                HostsFile::parse_line_with(&line, self.validation)
+            }
+        }))
+    }
+}
+
+/// The lines of a file being streamed. UTF-8 files are read a line at a
+/// time; UTF-16 ones are decoded whole first, since their lines cannot be
+/// split before they are decoded.
+pub enum FileLines {
+    Utf8(Lines<BufReader<File>>),
+    Decoded(std::vec::IntoIter<String>),
+}
+
+impl Iterator for FileLines {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            FileLines::Utf8(lines) => lines.next(),
+            FileLines::Decoded(lines) => lines.next().map(Ok),
+        }
+    }
+}
+
+impl HostsFile {
+    pub fn stream_from_file(path: &PathBuf) -> std::io::Result<ParsedLines<FileLines>> {
+        HostsFile::stream_from_file_with(path, Validation::default())
+    }
+
+    /// Streams the file in whichever encoding `read_from_file` would detect
+    /// for it.
+    pub fn stream_from_file_with(
+        path: &PathBuf,
+        validation: Validation,
+    ) -> std::io::Result<ParsedLines<FileLines>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let lines = match HostsFile::detect_encoding(reader.fill_buf()?) {
+            (Encoding::Utf8, _) => FileLines::Utf8(reader.lines()),
+            _ => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes)?;
+                let (lines, _) = HostsFile::decode(&bytes)?;
+                FileLines::Decoded(lines.into_iter())
+            }
+        };
+        Ok(HostsFile::stream_from_lines_with(lines, validation))
+    }
+
+    pub fn stream_from_lines<T>(lines: T) -> ParsedLines<T>
+    where
+        T: Iterator<Item = std::io::Result<String>>,
+    {
+        HostsFile::stream_from_lines_with(lines, Validation::default())
+    }
+
+    pub fn stream_from_lines_with<T>(lines: T, validation: Validation) -> ParsedLines<T>
+    where
+        T: Iterator<Item = std::io::Result<String>>,
+    {
+        ParsedLines {
+            lines,
+            first: true,
+            validation,
+        }
+    }
+
+    /// Passes every parsed line through `transform` and writes the lines it
+    /// keeps to `writer`. Columns are not aligned, since that would need the
+    /// whole file up front. Returns the number of lines written.
+    pub fn stream_to<T, W, F>(lines: T, writer: &mut W, transform: F) -> std::io::Result<usize>
+    where
+        T: Iterator<Item = std::io::Result<String>>,
+        W: Write,
+        F: FnMut(ParsedLine) -> Option<ParsedLine>,
+    {
+        HostsFile::stream_to_with(lines, writer, transform, Validation::default())
+    }
+
+    pub fn stream_to_with<T, W, F>(
+        lines: T,
+        writer: &mut W,
+        mut transform: F,
+        validation: Validation,
+    ) -> std::io::Result<usize>
+    where
+        T: Iterator<Item = std::io::Result<String>>,
+        W: Write,
+        F: FnMut(ParsedLine) -> Option<ParsedLine>,
+    {
+        let mut written = 0;
+        for line in HostsFile::stream_from_lines_with(lines, validation) {
+            if let Some(line) = transform(line?) {
+                writeln!(writer, "{}", line)?;
+                written += 1;
+            }
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::parse::ParsedLine;
+use super::HostsFile;
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+
+/// Values for the `${NAME}` references in templates. Variables set in the
+/// environment take precedence over those read from a `.env` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Variables {
+    pub values: HashMap<String, String>,
+}
+
+impl Variables {
+    pub fn from_env() -> Self {
+        Variables {
+            values: std::env::vars().collect(),
+        }
+    }
+
+    /// Reads `NAME=value` lines, as in a `.env` file. Values may be quoted
+    /// and lines may start with `export`. Variables that are already set
+    /// keep their value.
+    pub fn read_env_file(&mut self, path: &Path) -> Result<(), HostsError> {
+        let content = std::fs::read_to_string(path)?;
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = match line.split_once('=') {
+                Some((name, value)) if Variables::is_name(name.trim()) => {
+                    (name.trim(), value.trim())
+                }
+                _ => {
+                    return Err(HostsError::Parse(format!(
+                        "invalid variable on line {} of {}: {}",
+                        index + 1,
+                        path.display(),
+                        line
+                    )))
+                }
+            };
+            let value = Variables::unquote(value);
+            self.values
+                .entry(String::from(name))
+                .or_insert_with(|| String::from(value));
+        }
+        Ok(())
+    }
+
+    /// Replaces every `${NAME}` in `text` with the value of `NAME`.
+    pub fn substitute(&self, text: &str) -> Result<String, HostsError> {
+        let mut result = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after
+                .find('}')
+                .ok_or_else(|| HostsError::Parse(format!("unterminated variable in {}", text)))?;
+            let name = &after[..end];
+            if !Variables::is_name(name) {
+                return Err(HostsError::Parse(format!(
+                    "invalid variable name \"{}\" in {}",
+                    name, text
+                )));
+            }
+            match self.values.get(name) {
+                Some(value) => result.push_str(value),
+                None => {
+                    return Err(HostsError::Validation(format!(
+                        "variable {} is not set",
+                        name
+                    )))
+                }
+            }
+            rest = &after[end + 1..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    fn is_name(name: &str) -> bool {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    fn unquote(value: &str) -> &str {
+        for quote in ['"', '\''] {
+            if value.len() >= 2 && value.starts_with(quote) && value.ends_with(quote) {
+                return &value[1..value.len() - 1];
+            }
+        }
+        value
+    }
+}
+
+impl HostsFile {
+    /// Renders the templates and writes the file if an entry changed.
+    /// Returns the number of entries that changed.
+    pub fn render_templates_in_file(
+        &mut self,
+        destination_path: &PathBuf,
+        variables: &Variables,
+    ) -> Result<usize, HostsError> {
+        let before = self.render();
+        let rendered = HostsFile::render_templates_in_lines(&mut self.lines, variables)?;
+        self.write_journaled(
+            destination_path,
+            "render",
+            &format!("Rendered({})", rendered),
+            &before,
+        )?;
+        Ok(rendered)
+    }
+
+    /// Renders each `# template <ip> <host>...` line into the entry for the
+    /// same hostnames on the line below it, which is added if it is missing. An entry that was
+    /// commented out, e.g. by a profile, stays commented out. Returns the
+    /// number of entries that changed.
+    pub fn render_templates_in_lines(
+        lines: &mut Vec<ParsedLine>,
+        variables: &Variables,
+    ) -> Result<usize, HostsError> {
+        let mut rendered = 0;
+        let mut index = 0;
+        while index < lines.len() {
+            let template = match HostsFile::template(&lines[index]) {
+                Some(template) => template,
+                None => {
+                    index += 1;
+                    continue;
+                }
+            };
+            let entry = match HostsFile::parse_line(&variables.substitute(template)?) {
+                ParsedLine::Entry(entry) => entry,
+                line => {
+                    return Err(HostsError::Parse(format!(
+                        "template on line {} does not render to an entry: {}",
+                        index + 1,
+                        line
+                    )))
+                }
+            };
+            // Only an entry for the same hostnames was rendered from this
+            // template; anything else below it is left alone.
+            let same_hosts = |below: &HostsEntry| below.hosts().eq(entry.hosts());
+            let line = match lines.get(index + 1) {
+                Some(ParsedLine::Entry(below)) if same_hosts(below) => ParsedLine::Entry(entry),
+                Some(line)
+                    if line
+                        .disabled_entry()
+                        .is_some_and(|below| same_hosts(&below)) =>
+                {
+                    ParsedLine::disabled(&entry)
+                }
+                _ => {
+                    lines.insert(index + 1, ParsedLine::Entry(entry));
+                    rendered += 1;
+                    index += 2;
+                    continue;
+                }
+            };
+            if lines[index + 1] != line {
+                lines[index + 1] = line;
+                rendered += 1;
+            }
+            index += 2;
+        }
+        Ok(rendered)
+    }
+
+    fn template(line: &ParsedLine) -> Option<&str> {
+        match line {
+            ParsedLine::Comment(comment) => comment
+                .trim_start_matches('#')
+                .trim_start()
+                .strip_prefix("template ")
+                .map(str::trim),
+            _ => None,
+        }
+    }
+}
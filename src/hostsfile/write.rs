@@ -1,21 +1,29 @@
-use std::fs::OpenOptions;
-use std::io::{LineWriter, Write};
 use std::path::PathBuf;
 
+use super::elevate::Elevation;
+use super::encoding::TextFormat;
 use super::HostsFile;
+use crate::error::HostsError;
 
 impl HostsFile {
-    pub fn write(&self, path: &PathBuf) -> std::io::Result<()> {
-        let file = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(path)?;
-        let mut file = LineWriter::new(file);
-        for line in self.lines.iter() {
-            writeln!(file, "{}", line)?;
-        }
-        file.flush()?;
-        Ok(())
+    /// Writes the file, through the elevation command from the environment
+    /// if the file may not be written directly.
+    pub fn write(&self, path: &PathBuf) -> Result<(), HostsError> {
+        HostsFile::write_bytes(
+            path,
+            &HostsFile::encode(&self.render(), &self.text_format),
+            Elevation::from_env().as_ref(),
+        )
+    }
+
+    /// Writes `lines` exactly as they are, in `text_format`.
+    pub fn write_snapshot(
+        path: &PathBuf,
+        lines: Vec<String>,
+        text_format: &TextFormat,
+    ) -> Result<(), HostsError> {
+        let mut file = HostsFile::read_from_lines(lines.into_iter().map(Ok))?;
+        file.text_format = text_format.clone();
+        file.write(path)
     }
 }
@@ -1,2 +1,11 @@
+pub mod audit;
+pub mod cidr;
+pub mod completions;
+pub mod dns;
+pub mod error;
+pub mod hostsentry;
 pub mod hostsfile;
-pub mod hostsentry;
\ No newline at end of file
+pub mod policy;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod validation;
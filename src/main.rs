@@ -1,15 +1,37 @@
-use std::path::PathBuf;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
 
 use argh::FromArgs;
 use colored::Colorize;
 
+use hosts::audit::{self, Audit, AuditFilter};
+use hosts::cidr::Ipv4Cidr;
+use hosts::completions::Shell;
+use hosts::dns::DnsServer;
+use hosts::error::HostsError;
 use hosts::hostsentry::HostsEntry;
 use hosts::hostsfile::add::AddResult;
+use hosts::hostsfile::export::ResolverFormat;
+use hosts::hostsfile::journal::{JournalRecord, JournalState};
+use hosts::hostsfile::profile::ProfileState;
+use hosts::hostsfile::query::{IpHosts, Selector};
 use hosts::hostsfile::remove::RemoveResult;
+use hosts::hostsfile::template::Variables;
 use hosts::hostsfile::HostsFile;
+use hosts::validation::Validation;
 
 #[derive(FromArgs, PartialEq, Debug)]
 /// Manage [IP hostname] entries in your hosts file.
+#[argh(
+    error_code(1, "fmt --check found unformatted lines"),
+    error_code(2, "an argument or input line could not be parsed"),
+    error_code(3, "the input is not acceptable"),
+    error_code(4, "nothing matched the hostname or IP"),
+    error_code(5, "the change conflicts with the file"),
+    error_code(6, "permission denied"),
+    error_code(7, "another process kept the file locked too long"),
+    error_code(8, "any other I/O error")
+)]
 struct TopLevel {
     #[argh(subcommand)]
     subcommands: Option<SubCommands>,
@@ -24,12 +46,71 @@ enum SubCommands {
     List(ListCommand),
     Add(AddCommand),
     Remove(RemoveCommand),
+    Disable(DisableCommand),
+    Enable(EnableCommand),
+    Undo(UndoCommand),
+    Redo(RedoCommand),
+    History(HistoryCommand),
+    Log(LogCommand),
+    Fmt(FmtCommand),
+    Compact(CompactCommand),
+    Expand(ExpandCommand),
+    Serve(ServeCommand),
+    Export(ExportCommand),
+    Import(ImportCommand),
+    Who(WhoCommand),
+    Resolve(ResolveCommand),
+    Rename(RenameCommand),
+    Retarget(RetargetCommand),
+    Render(RenderCommand),
+    Profile(ProfileCommand),
+    #[cfg(feature = "tui")]
+    Tui(TuiCommand),
+    Completions(CompletionsCommand),
 }
 
 #[derive(FromArgs, PartialEq, Debug)]
 /// List the entries in your hosts file.
 #[argh(subcommand, name = "list")]
 struct ListCommand {
+    /// only list entries with an IP in this network, e.g. 10.0.0.0/8
+    #[argh(option)]
+    cidr: Option<Ipv4Cidr>,
+    /// list each IP once with every hostname that points at it
+    #[argh(switch)]
+    by_ip: bool,
+    /// how strictly hostnames are checked: strict, resolver (default) or
+    /// permissive
+    #[argh(option, default = "Validation::default()")]
+    validation: Validation,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the hostnames that point at an IP.
+#[argh(subcommand, name = "who")]
+struct WhoCommand {
+    #[argh(positional)]
+    /// IP to look up
+    ip: Ipv4Addr,
+    /// how strictly hostnames are checked: strict, resolver (default) or
+    /// permissive
+    #[argh(option, default = "Validation::default()")]
+    validation: Validation,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the IPs a hostname points at.
+#[argh(subcommand, name = "resolve")]
+struct ResolveCommand {
+    #[argh(positional)]
+    /// hostname to look up
+    host: String,
     /// path to hosts file (defaults to system path)
     #[argh(option, default = "String::from(HostsFile::PATH)")]
     path: String,
@@ -40,11 +121,18 @@ struct ListCommand {
 #[argh(subcommand, name = "add")]
 struct AddCommand {
     #[argh(positional)]
-    /// IP of the entry to add
-    ip: String,
-    #[argh(positional)]
-    /// hostname of the entry to add
-    host: String,
+    /// IP of the entry to add, followed by its hostname(s)
+    entry: Vec<String>,
+    /// read "ip host..." lines from stdin instead
+    #[argh(switch)]
+    stdin: bool,
+    /// do not warn about hostnames outside the domains the policy allows
+    #[argh(switch)]
+    force: bool,
+    /// how strictly hostnames are checked: strict, resolver (default) or
+    /// permissive
+    #[argh(option, default = "Validation::default()")]
+    validation: Validation,
     /// path to hosts file (defaults to system path)
     #[argh(option, default = "String::from(HostsFile::PATH)")]
     path: String,
@@ -56,49 +144,507 @@ struct AddCommand {
 struct RemoveCommand {
     #[argh(positional)]
     /// IP or hostname of the entry to remove
-    entry: String,
+    entry: Option<String>,
+    /// remove every entry with an IP in this network, e.g. 10.0.0.0/8
+    #[argh(option)]
+    cidr: Option<Ipv4Cidr>,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Change a hostname, keeping its IP, line and comment.
+#[argh(subcommand, name = "rename")]
+struct RenameCommand {
+    #[argh(positional)]
+    /// hostname to rename
+    old: String,
+    #[argh(positional)]
+    /// hostname to rename it to
+    new: String,
+    /// rename even if the new hostname is already in use
+    #[argh(switch)]
+    merge: bool,
+    /// how strictly hostnames are checked: strict, resolver (default) or
+    /// permissive
+    #[argh(option, default = "Validation::default()")]
+    validation: Validation,
+    /// do not warn about hostnames outside the domains the policy allows
+    #[argh(switch)]
+    force: bool,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Move every hostname on an IP to another IP.
+#[argh(subcommand, name = "retarget")]
+struct RetargetCommand {
+    #[argh(positional)]
+    /// IP the hostnames point at now
+    old_ip: Ipv4Addr,
+    #[argh(positional)]
+    /// IP to point them at
+    new_ip: Ipv4Addr,
+    /// do not warn about hostnames outside the domains the policy allows
+    #[argh(switch)]
+    force: bool,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Re-render the entries below "# template <ip> <hostname>" lines.
+#[argh(subcommand, name = "render")]
+struct RenderCommand {
+    /// read variables from this .env file too
+    #[argh(option)]
+    env: Option<String>,
+    /// do not warn about hostnames outside the domains the policy allows
+    #[argh(switch)]
+    force: bool,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Comment out an entry (or entries) without removing it.
+#[argh(subcommand, name = "disable")]
+struct DisableCommand {
+    #[argh(positional)]
+    /// IP or hostname of the entry to disable
+    entry: Option<String>,
+    /// disable every entry with an IP in this network, e.g. 10.0.0.0/8
+    #[argh(option)]
+    cidr: Option<Ipv4Cidr>,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Uncomment an entry (or entries) disabled earlier.
+#[argh(subcommand, name = "enable")]
+struct EnableCommand {
+    #[argh(positional)]
+    /// IP or hostname of the entry to enable
+    entry: Option<String>,
+    /// enable every entry with an IP in this network, e.g. 10.0.0.0/8
+    #[argh(option)]
+    cidr: Option<Ipv4Cidr>,
+    /// do not warn about hostnames outside the domains the policy allows
+    #[argh(switch)]
+    force: bool,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Revert the last change(s) made to the hosts file.
+#[argh(subcommand, name = "undo")]
+struct UndoCommand {
+    #[argh(positional, default = "1")]
+    /// number of changes to revert (defaults to 1)
+    count: usize,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Re-apply the last undone change(s).
+#[argh(subcommand, name = "redo")]
+struct RedoCommand {
+    #[argh(positional, default = "1")]
+    /// number of changes to re-apply (defaults to 1)
+    count: usize,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the changes recorded in the journal.
+#[argh(subcommand, name = "history")]
+struct HistoryCommand {
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Show the audit log of every change made to the hosts file.
+#[argh(subcommand, name = "log")]
+struct LogCommand {
+    /// only show changes to entries with this hostname
+    #[argh(option)]
+    host: Option<String>,
+    /// only show changes made at or after this time, in seconds since the
+    /// epoch or as YYYY-MM-DD[THH:MM:SS] UTC
+    #[argh(option, from_str_fn(parse_time))]
+    since: Option<u64>,
+    /// only show changes made at or before this time
+    #[argh(option, from_str_fn(parse_time))]
+    until: Option<u64>,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Rewrite the hosts file with aligned columns and normalised separators.
+#[argh(subcommand, name = "fmt")]
+struct FmtCommand {
+    /// only report whether the file is formatted, exit with 1 if not
+    #[argh(switch)]
+    check: bool,
+    /// sort the entries within each section
+    #[argh(switch)]
+    sort: bool,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Merge hostnames that share an IP onto as few lines as possible.
+#[argh(subcommand, name = "compact")]
+struct CompactCommand {
+    /// maximum number of hostnames per line (defaults to 9)
+    #[argh(option, default = "HostsFile::ALIAS_LIMIT")]
+    limit: usize,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Split lines with several hostnames into one line per hostname.
+#[argh(subcommand, name = "expand")]
+struct ExpandCommand {
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[cfg(feature = "tui")]
+#[derive(FromArgs, PartialEq, Debug)]
+/// Browse and edit the hosts file full screen.
+#[argh(subcommand, name = "tui")]
+struct TuiCommand {
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Switch between named sections of entries.
+#[argh(subcommand, name = "profile")]
+struct ProfileCommand {
+    #[argh(subcommand)]
+    action: ProfileAction,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand)]
+enum ProfileAction {
+    Use(ProfileUseCommand),
+    List(ProfileListCommand),
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Enable a profile and comment out every other one.
+#[argh(subcommand, name = "use")]
+struct ProfileUseCommand {
+    #[argh(positional)]
+    /// name of the profile, as in "# profile <name>"
+    name: String,
+    /// do not warn about hostnames outside the domains the policy allows
+    #[argh(switch)]
+    force: bool,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// List the profiles and which of them are active.
+#[argh(subcommand, name = "list")]
+struct ProfileListCommand {
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Print a completion script for bash, zsh or fish.
+#[argh(subcommand, name = "completions")]
+struct CompletionsCommand {
+    #[argh(positional)]
+    /// shell to complete in: bash, zsh or fish
+    shell: Shell,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Print the entries as configuration for another resolver.
+#[argh(subcommand, name = "export")]
+struct ExportCommand {
+    /// format to export to: dnsmasq, dnsmasq-host-record, unbound,
+    /// bind-zone, coredns or pihole
+    #[argh(option)]
+    to: ResolverFormat,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Add the host overrides of another resolver's configuration file.
+#[argh(subcommand, name = "import")]
+struct ImportCommand {
+    /// format to import from: dnsmasq, dnsmasq-host-record or unbound
+    #[argh(option)]
+    from: ResolverFormat,
+    /// configuration file to import
+    #[argh(positional)]
+    file: String,
+    /// how strictly hostnames are checked: strict, resolver (default) or
+    /// permissive
+    #[argh(option, default = "Validation::default()")]
+    validation: Validation,
+    /// do not warn about hostnames outside the domains the policy allows
+    #[argh(switch)]
+    force: bool,
+    /// path to hosts file (defaults to system path)
+    #[argh(option, default = "String::from(HostsFile::PATH)")]
+    path: String,
+}
+
+#[derive(FromArgs, PartialEq, Debug)]
+/// Answer DNS queries over UDP from the entries in your hosts file.
+#[argh(subcommand, name = "serve")]
+struct ServeCommand {
+    /// address to listen on (defaults to 127.0.0.1:5353)
+    #[argh(option, default = "String::from(\"127.0.0.1:5353\")")]
+    listen: String,
     /// path to hosts file (defaults to system path)
     #[argh(option, default = "String::from(HostsFile::PATH)")]
     path: String,
 }
 
 pub fn main() {
-    let command: TopLevel = argh::from_env();
-    match command.subcommands {
+    // The entry point of the completion scripts stays out of the help.
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    if arguments.first().map(|argument| argument.as_str())
+        == Some(hosts::completions::COMPLETE_COMMAND)
+    {
+        for candidate in hosts::completions::complete(&arguments[1..]) {
+            println!("{}", candidate);
+        }
+        return;
+    }
+    let TopLevel { subcommands, path } = parse_arguments(&arguments);
+    // A subcommand's own --path wins over the one given before it.
+    let hosts_path = |subcommand_path: &str| match subcommand_path {
+        HostsFile::PATH => PathBuf::from(&path),
+        subcommand_path => PathBuf::from(subcommand_path),
+    };
+    match subcommands {
         Some(subcommand) => match subcommand {
-            SubCommands::List(parameters) => list_entries(&PathBuf::from(&parameters.path)),
-            SubCommands::Add(parameters) => add_entry(
-                &PathBuf::from(&parameters.path),
+            SubCommands::List(parameters) if parameters.by_ip => list_by_ip(
+                &hosts_path(&parameters.path),
+                parameters.cidr,
+                parameters.validation,
+            ),
+            SubCommands::List(parameters) => list_entries(
+                &hosts_path(&parameters.path),
+                parameters.cidr,
+                parameters.validation,
+            ),
+            SubCommands::Who(parameters) => who(
+                &hosts_path(&parameters.path),
                 parameters.ip,
-                parameters.host,
+                parameters.validation,
+            ),
+            SubCommands::Resolve(parameters) => {
+                resolve(&hosts_path(&parameters.path), &parameters.host)
+            }
+            SubCommands::Add(parameters) => {
+                let path = hosts_path(&parameters.path);
+                let (force, validation) = (parameters.force, parameters.validation);
+                match (parameters.entry.as_slice(), parameters.stdin) {
+                    ([], true) => add_from_stdin(&path, force, validation),
+                    ([ip, hosts @ ..], false) if !hosts.is_empty() => {
+                        add_hosts(&path, ip, hosts, force, validation)
+                    }
+                    (_, true) => fail(HostsError::Parse(String::from(
+                        "give either entries or --stdin, not both",
+                    ))),
+                    (_, false) => fail(HostsError::Parse(String::from(
+                        "give an IP and at least one hostname, or --stdin",
+                    ))),
+                }
+            }
+            SubCommands::Remove(parameters) => match (parameters.entry, parameters.cidr) {
+                (_, Some(network)) => remove_network(&hosts_path(&parameters.path), network),
+                (Some(entry), None) => remove_entry(&hosts_path(&parameters.path), entry),
+                (None, None) => fail(HostsError::Parse(String::from(
+                    "give an IP, a hostname or --cidr",
+                ))),
+            },
+            SubCommands::Rename(parameters) => rename_host(
+                &hosts_path(&parameters.path),
+                parameters.old,
+                parameters.new,
+                parameters.merge,
+                parameters.validation,
+                parameters.force,
+            ),
+            SubCommands::Retarget(parameters) => retarget_ip(
+                &hosts_path(&parameters.path),
+                parameters.old_ip,
+                parameters.new_ip,
+                parameters.force,
+            ),
+            SubCommands::Render(parameters) => render_templates(
+                &hosts_path(&parameters.path),
+                parameters.env.map(PathBuf::from),
+                parameters.force,
+            ),
+            SubCommands::Disable(parameters) => toggle_entries(
+                &hosts_path(&parameters.path),
+                select(parameters.entry, parameters.cidr),
+                false,
+                false,
+            ),
+            SubCommands::Enable(parameters) => toggle_entries(
+                &hosts_path(&parameters.path),
+                select(parameters.entry, parameters.cidr),
+                true,
+                parameters.force,
+            ),
+            SubCommands::Undo(parameters) => {
+                undo_changes(&hosts_path(&parameters.path), parameters.count)
+            }
+            SubCommands::Redo(parameters) => {
+                redo_changes(&hosts_path(&parameters.path), parameters.count)
+            }
+            SubCommands::History(parameters) => list_history(&hosts_path(&parameters.path)),
+            SubCommands::Log(parameters) => show_log(
+                &hosts_path(&parameters.path),
+                &AuditFilter {
+                    host: parameters.host,
+                    since: parameters.since,
+                    until: parameters.until,
+                },
+            ),
+            SubCommands::Fmt(parameters) => format_file(
+                &hosts_path(&parameters.path),
+                parameters.check,
+                parameters.sort,
+            ),
+            SubCommands::Compact(parameters) => {
+                compact_file(&hosts_path(&parameters.path), parameters.limit)
+            }
+            SubCommands::Expand(parameters) => expand_file(&hosts_path(&parameters.path)),
+            SubCommands::Export(parameters) => {
+                export_entries(&hosts_path(&parameters.path), parameters.to)
+            }
+            SubCommands::Import(parameters) => import_entries(
+                &hosts_path(&parameters.path),
+                &PathBuf::from(&parameters.file),
+                parameters.from,
+                parameters.validation,
+                parameters.force,
             ),
-            SubCommands::Remove(parameters) => {
-                remove_entry(&PathBuf::from(&parameters.path), parameters.entry)
+            #[cfg(feature = "tui")]
+            SubCommands::Tui(parameters) => {
+                if let Err(error) = hosts::tui::run(&hosts_path(&parameters.path)) {
+                    fail(error);
+                }
+            }
+            SubCommands::Profile(parameters) => match parameters.action {
+                ProfileAction::Use(parameters) => use_profile(
+                    &hosts_path(&parameters.path),
+                    &parameters.name,
+                    parameters.force,
+                ),
+                ProfileAction::List(parameters) => list_profiles(&hosts_path(&parameters.path)),
+            },
+            SubCommands::Completions(parameters) => {
+                print!("{}", hosts::completions::script(parameters.shell))
+            }
+            SubCommands::Serve(parameters) => {
+                serve(&hosts_path(&parameters.path), &parameters.listen)
             }
         },
-        None => list_entries(&PathBuf::from(&command.path)),
+        None => list_entries(&PathBuf::from(&path), None, Validation::default()),
     }
 }
 
-fn list_entries(path: &PathBuf) {
-    let file = match HostsFile::read_from_file(path) {
-        Ok(file) => file,
-        Err(error) => {
-            eprintln!("Error: {}", error);
-            std::process::exit(1);
+/// Parses the arguments like `argh::from_env`, but exits with the code of
+/// `HostsError::Parse` when they cannot be parsed.
+fn parse_arguments(arguments: &[String]) -> TopLevel {
+    let arguments: Vec<&str> = arguments.iter().map(|argument| argument.as_str()).collect();
+    match TopLevel::from_args(&["hosts"], &arguments) {
+        Ok(command) => command,
+        Err(early_exit) if early_exit.status.is_ok() => {
+            println!("{}", early_exit.output);
+            std::process::exit(0);
+        }
+        Err(early_exit) => {
+            eprintln!(
+                "{}\nRun hosts --help for more information.",
+                early_exit.output
+            );
+            std::process::exit(HostsError::Parse(early_exit.output).exit_code());
         }
+    }
+}
+
+/// Prints the error and exits with its exit code.
+fn fail(error: impl Into<HostsError>) -> ! {
+    let error = error.into();
+    eprintln!("Error: {}", error);
+    std::process::exit(error.exit_code());
+}
+
+fn list_entries(path: &PathBuf, network: Option<Ipv4Cidr>, validation: Validation) {
+    let file = match HostsFile::read_from_file_with(path, validation) {
+        Ok(file) => file,
+        Err(error) => fail(error),
     };
-    let entries = file.entries_count();
-    if entries == 1 {
-        println!("Found 1 entry in {}", path.to_string_lossy());
+    let entries = match network {
+        Some(network) => file
+            .find_in_network(network)
+            .map(|(_, entry)| entry)
+            .collect(),
+        None => file.entries(),
+    };
+    let location = match network {
+        Some(network) => format!("{} within {}", path.to_string_lossy(), network),
+        None => path.to_string_lossy().to_string(),
+    };
+    if entries.len() == 1 {
+        println!("Found 1 entry in {}", location);
     } else {
-        println!("Found {} entries in {}", entries, path.to_string_lossy());
+        println!("Found {} entries in {}", entries.len(), location);
     }
-    for entry in file.entries() {
+    for entry in entries {
+        let hosts = entry
+            .hosts()
+            .map(HostsEntry::unicode_host)
+            .collect::<Vec<String>>()
+            .join(" ");
         println!(
             "{} {}",
             format!("{:width$}", entry.ip, width = file.ip_width).bright_yellow(),
-            format!("{}", entry.host).bright_cyan()
+            hosts.bright_cyan()
         );
     }
     let invalids = file.invalids_count();
@@ -115,110 +661,717 @@ fn list_entries(path: &PathBuf) {
             );
         }
         for entry in file.invalids() {
-            println!("{}", entry);
+            match HostsFile::try_parse_line(entry, validation) {
+                Err(error) => println!("{} {}", entry, format!("({})", error).dimmed()),
+                Ok(_) => println!("{}", entry),
+            }
         }
     };
+    let mixed = &file.text_format.mixed_line_endings;
+    if !mixed.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "Found mixed line endings, {} line(s) do not end with {}",
+                mixed.len(),
+                file.text_format.line_ending
+            )
+            .bright_white()
+            .on_bright_red()
+        );
+        println!(
+            "{}",
+            mixed
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
 }
 
-fn add_entry(path: &PathBuf, ip: String, host: String) {
-    let mut file = match HostsFile::read_from_file(path) {
-        Ok(file) => file,
-        Err(error) => {
-            eprintln!("Error: {}", error);
-            std::process::exit(1);
+fn add_hosts(path: &PathBuf, ip: &str, hosts: &[String], force: bool, validation: Validation) {
+    let mut entries = Vec::with_capacity(hosts.len());
+    for host in hosts.iter() {
+        match HostsEntry::parse_with(ip, host, validation) {
+            Ok(entry) => entries.push(entry),
+            Err(error) => fail(error),
         }
+    }
+    add_entries(
+        path,
+        &entries,
+        &format!("add {} {}", ip, hosts.join(" ")),
+        force,
+        validation,
+    );
+}
+
+fn add_from_stdin(path: &PathBuf, force: bool, validation: Validation) {
+    let lines = match std::io::stdin()
+        .lines()
+        .collect::<std::io::Result<Vec<String>>>()
+    {
+        Ok(lines) => lines,
+        Err(error) => fail(error),
+    };
+    match HostsFile::entries_from_lines_with(&lines, validation) {
+        Ok(entries) => add_entries(path, &entries, "add --stdin", force, validation),
+        Err(error) => fail(error),
+    }
+}
+
+fn add_entries(
+    path: &PathBuf,
+    entries: &[HostsEntry],
+    operation: &str,
+    force: bool,
+    validation: Validation,
+) {
+    let (_lock, mut file) = match HostsFile::read_locked_with(path, validation) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
     };
-    let entry = match HostsEntry::parse(&ip, &host) {
-        Ok(entry) => entry,
-        Err(error) => {
-            eprintln!("Error: {}", error);
-            std::process::exit(1);
+    let actions = match file.add_all_to_file(path, entries, operation) {
+        Ok(actions) => actions,
+        Err(error) => fail(error),
+    };
+    for (entry, action) in entries.iter().zip(actions.iter()) {
+        let ip = format!("{}", entry.ip).bright_yellow();
+        let host = HostsEntry::unicode_host(&entry.host).bright_cyan();
+        match action {
+            AddResult::Added => println!("added    {} {}", ip, host),
+            AddResult::Updated(previous_ip) => println!(
+                "updated  {} > {} {}",
+                format!("{}", previous_ip).bright_yellow(),
+                ip,
+                host
+            ),
+            AddResult::Skipped => println!("exists   {} {}", ip, host),
         }
+    }
+    let (added, updated, skipped) = HostsFile::count_actions(&actions);
+    println!(
+        "{} added, {} updated, {} already existed",
+        added.to_string().bright_yellow(),
+        updated.to_string().bright_yellow(),
+        skipped.to_string().bright_yellow()
+    );
+    warn_unlisted(&file, force);
+}
+
+fn list_by_ip(path: &PathBuf, network: Option<Ipv4Cidr>, validation: Validation) {
+    let file = match HostsFile::read_from_file_with(path, validation) {
+        Ok(file) => file,
+        Err(error) => fail(error),
     };
-    match file.add_to_file(path, &entry) {
-        Ok(action) => match action {
-            AddResult::Added => {
-                println!("Entry added");
-                println!(
-                    "{} {}",
-                    format!("{}", entry.ip).bright_yellow(),
-                    format!("{}", entry.host).bright_cyan()
-                );
-            }
-            AddResult::Updated(previous_ip) => {
-                println!("Existing entry updated");
-                println!(
-                    "{} > {} {}",
-                    format!("{}", previous_ip).bright_yellow(),
-                    format!("{}", entry.ip).bright_yellow(),
-                    format!("{}", entry.host).bright_cyan()
-                );
-            }
-            AddResult::Skipped => {
-                println!("Entry already exists");
+    let groups: Vec<IpHosts> = file
+        .group_by_ip()
+        .into_iter()
+        .filter(|group| match network {
+            Some(network) => network.contains(&group.ip),
+            None => true,
+        })
+        .collect();
+    let location = match network {
+        Some(network) => format!("{} within {}", path.to_string_lossy(), network),
+        None => path.to_string_lossy().to_string(),
+    };
+    if groups.len() == 1 {
+        println!("Found 1 IP in {}", location);
+    } else {
+        println!("Found {} IPs in {}", groups.len(), location);
+    }
+    let ip_width = groups
+        .iter()
+        .map(|group| HostsFile::ip_width(&group.ip))
+        .max()
+        .unwrap_or(0);
+    for group in groups {
+        println!(
+            "{} {}",
+            format!("{:width$}", group.ip, width = ip_width).bright_yellow(),
+            ip_hosts(&group)
+        );
+    }
+}
+
+fn who(path: &PathBuf, ip: Ipv4Addr, validation: Validation) {
+    let file = match HostsFile::read_from_file_with(path, validation) {
+        Ok(file) => file,
+        Err(error) => fail(error),
+    };
+    match file.hosts_for_ip(ip) {
+        Some(group) => {
+            let count = group.hosts.len() + group.disabled.len();
+            if count == 1 {
+                println!("Found 1 hostname for {}", ip.to_string().bright_yellow());
+            } else {
                 println!(
-                    "{} {}",
-                    format!("{}", entry.ip).bright_yellow(),
-                    format!("{}", entry.host).bright_cyan()
+                    "Found {} hostnames for {}",
+                    count,
+                    ip.to_string().bright_yellow()
                 );
             }
-        },
-        Err(error) => {
-            eprintln!("Error: {}", error);
-            std::process::exit(1);
+            println!("{}", ip_hosts(&group));
         }
+        None => fail(HostsError::NotFound(format!(
+            "no hostname points at {}",
+            ip
+        ))),
     }
 }
 
-fn remove_entry(path: &PathBuf, entry: String) {
-    let mut file = match HostsFile::read_from_file(path) {
+fn resolve(path: &PathBuf, host: &str) {
+    let file = match HostsFile::read_from_file(path) {
         Ok(file) => file,
-        Err(error) => {
-            eprintln!("Error: {}", error);
-            std::process::exit(1);
+        Err(error) => fail(error),
+    };
+    let host = match HostsEntry::parse_host(host) {
+        Ok(host) => host,
+        Err(error) => fail(error),
+    };
+    let mut ips: Vec<Ipv4Addr> = Vec::new();
+    for (_, entry) in file.find_by_host(&host) {
+        if !ips.contains(&entry.ip) {
+            ips.push(entry.ip);
         }
+    }
+    if ips.is_empty() {
+        fail(HostsError::NotFound(format!(
+            "{} does not point at any IP",
+            HostsEntry::unicode_host(&host)
+        )));
+    }
+    for ip in ips {
+        println!("{}", ip.to_string().bright_yellow());
+    }
+}
+
+/// The hostnames of an IP, with the disabled ones dimmed after the others.
+fn ip_hosts(group: &IpHosts) -> String {
+    let hosts = group
+        .hosts
+        .iter()
+        .map(|host| HostsEntry::unicode_host(host).bright_cyan().to_string());
+    let disabled = group.disabled.iter().map(|host| {
+        format!("{} (disabled)", HostsEntry::unicode_host(host))
+            .dimmed()
+            .to_string()
+    });
+    hosts.chain(disabled).collect::<Vec<String>>().join(" ")
+}
+
+/// Prints the policy warnings of the last write to `file`, unless `force`
+/// silences them.
+fn warn_unlisted(file: &HostsFile, force: bool) {
+    if force {
+        return;
+    }
+    for warning in file.warnings.iter() {
+        eprintln!(
+            "{} {}, pass --force to silence this",
+            "Warning:".bright_red(),
+            warning
+        );
+    }
+}
+
+fn remove_entry(path: &PathBuf, entry: String) {
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
     };
     match file.remove_from_file(path, &entry) {
         Ok(action) => match action {
             RemoveResult::RemovedByIp(amount) => {
                 if amount == 1 {
-                    println!(
-                        "Entry with ip {} removed",
-                        format!("{}", entry).bright_yellow()
-                    );
+                    println!("Entry with ip {} removed", entry.bright_yellow());
                 } else {
                     println!(
                         "Removed {} entries with ip {}",
                         amount,
-                        format!("{}", entry).bright_yellow()
+                        entry.bright_yellow()
                     );
                 }
             }
             RemoveResult::RemovedByHost(amount) => {
                 if amount == 1 {
-                    println!(
-                        "Entry with hostname {} removed",
-                        format!("{}", entry).bright_cyan()
-                    );
+                    println!("Entry with hostname {} removed", entry.bright_cyan());
                 } else {
                     println!(
                         "Removed {} entries with hostname {}",
                         amount,
-                        format!("{}", entry).bright_cyan()
+                        entry.bright_cyan()
                     );
                 }
             }
-            RemoveResult::Invalid => {
+        },
+        Err(error) => fail(error),
+    }
+}
+
+fn rename_host(
+    path: &PathBuf,
+    old: String,
+    new: String,
+    merge: bool,
+    validation: Validation,
+    force: bool,
+) {
+    let (_lock, mut file) = match HostsFile::read_locked_with(path, validation) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    match file.rename_in_file_with(path, &old, &new, merge, validation) {
+        Ok(1) => println!("Renamed {} to {}", old.bright_cyan(), new.bright_cyan()),
+        Ok(amount) => println!(
+            "Renamed {} to {} on {} lines",
+            old.bright_cyan(),
+            new.bright_cyan(),
+            amount
+        ),
+        Err(error) => fail(error),
+    }
+    warn_unlisted(&file, force);
+}
+
+fn render_templates(path: &PathBuf, env_path: Option<PathBuf>, force: bool) {
+    let mut variables = Variables::from_env();
+    if let Some(env_path) = env_path {
+        if let Err(error) = variables.read_env_file(&env_path) {
+            fail(error);
+        }
+    }
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    match file.render_templates_in_file(path, &variables) {
+        Ok(0) => println!("Templates are up to date"),
+        Ok(1) => println!("Rendered 1 entry"),
+        Ok(amount) => println!("Rendered {} entries", amount),
+        Err(error) => fail(error),
+    }
+    warn_unlisted(&file, force);
+}
+
+fn retarget_ip(path: &PathBuf, old_ip: Ipv4Addr, new_ip: Ipv4Addr, force: bool) {
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    match file.retarget_in_file(path, old_ip, new_ip) {
+        Ok(hosts) => {
+            if hosts.len() == 1 {
                 println!(
-                    "Error: invalid entry {}",
-                    format!("{}", entry).bright_white().on_bright_red()
+                    "Moved 1 hostname from {} to {}",
+                    old_ip.to_string().bright_yellow(),
+                    new_ip.to_string().bright_yellow()
+                );
+            } else {
+                println!(
+                    "Moved {} hostnames from {} to {}",
+                    hosts.len(),
+                    old_ip.to_string().bright_yellow(),
+                    new_ip.to_string().bright_yellow()
                 );
-                std::process::exit(0);
             }
-        },
-        Err(error) => {
-            eprintln!("Error: {}", error);
-            std::process::exit(1);
+            for host in hosts {
+                println!("{}", HostsEntry::unicode_host(&host).bright_cyan());
+            }
+        }
+        Err(error) => fail(error),
+    }
+    warn_unlisted(&file, force);
+}
+
+fn undo_changes(path: &PathBuf, count: usize) {
+    match HostsFile::undo(path, count) {
+        Ok(records) => {
+            if records.is_empty() {
+                println!("Nothing to undo");
+            } else if records.len() == 1 {
+                println!("Undid 1 change");
+            } else {
+                println!("Undid {} changes", records.len());
+            }
+            for record in records.iter() {
+                print_record(record);
+            }
         }
+        Err(error) => fail(error),
+    }
+}
+
+fn redo_changes(path: &PathBuf, count: usize) {
+    match HostsFile::redo(path, count) {
+        Ok(records) => {
+            if records.is_empty() {
+                println!("Nothing to redo");
+            } else if records.len() == 1 {
+                println!("Redid 1 change");
+            } else {
+                println!("Redid {} changes", records.len());
+            }
+            for record in records.iter() {
+                print_record(record);
+            }
+        }
+        Err(error) => fail(error),
+    }
+}
+
+fn list_history(path: &Path) {
+    let records = match HostsFile::history(path) {
+        Ok(records) => records,
+        Err(error) => fail(error),
+    };
+    if records.len() == 1 {
+        println!("Found 1 change in the journal");
+    } else {
+        println!("Found {} changes in the journal", records.len());
+    }
+    for (index, record) in records.iter().enumerate().rev() {
+        let state = match record.state {
+            JournalState::Applied => record.state.to_string().bright_green(),
+            JournalState::Undone => record.state.to_string().bright_black(),
+        };
+        print!("{:>4} {:7} ", index + 1, state);
+        print_record(record);
+    }
+}
+
+fn parse_time(value: &str) -> Result<u64, String> {
+    audit::parse_time(value).map_err(|error| error.to_string())
+}
+
+fn show_log(path: &Path, filter: &AuditFilter) {
+    let (records, skipped) = match Audit::read(path, filter) {
+        Ok(read) => read,
+        Err(error) => fail(error),
+    };
+    for line in skipped {
+        eprintln!(
+            "{} skipped line {} of the audit log, which is not a record",
+            "Warning:".bright_red(),
+            line
+        );
+    }
+    if records.len() == 1 {
+        println!("Found 1 change in the audit log");
+    } else {
+        println!("Found {} changes in the audit log", records.len());
+    }
+    for record in records.iter() {
+        let mut user = match (&record.user, record.uid) {
+            (Some(user), Some(uid)) => format!("{} ({})", user, uid),
+            (Some(user), None) => user.clone(),
+            (None, Some(uid)) => format!("uid {}", uid),
+            (None, None) => String::from("unknown user"),
+        };
+        match &record.claimed_user {
+            Some(claimed) if record.user.as_ref() != Some(claimed) => {
+                user = format!("{}, claimed {}", user, claimed)
+            }
+            _ => {}
+        }
+        println!(
+            "{} {} {}",
+            audit::format_time(record.time).bright_white(),
+            user.bright_yellow(),
+            record.operation.bright_cyan()
+        );
+        println!("    {}", record.command.join(" ").bright_black());
+        for line in record.before.iter() {
+            println!("    {}", format!("-{}", line).bright_red());
+        }
+        for line in record.after.iter() {
+            println!("    {}", format!("+{}", line).bright_green());
+        }
+    }
+}
+
+fn print_record(record: &JournalRecord) {
+    println!(
+        "{} {}",
+        record.operation.bright_cyan(),
+        format!("({})", record.result).bright_white()
+    );
+}
+
+fn format_file(path: &PathBuf, check: bool, sort: bool) {
+    let result = if check {
+        HostsFile::check_format(path, sort)
+    } else {
+        HostsFile::format_file(path, sort)
+    };
+    let lines = match result {
+        Ok(lines) => lines,
+        Err(error) => fail(error),
+    };
+    if lines.is_empty() {
+        println!("{} is already formatted", path.to_string_lossy());
+        return;
+    }
+    let numbers = lines
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    if check {
+        if lines.len() == 1 {
+            println!("Found 1 unformatted line in {}", path.to_string_lossy());
+        } else {
+            println!(
+                "Found {} unformatted lines in {}",
+                lines.len(),
+                path.to_string_lossy()
+            );
+        }
+        println!("{}", numbers.bright_yellow());
+        std::process::exit(1);
+    }
+    if lines.len() == 1 {
+        println!("Formatted 1 line");
+    } else {
+        println!("Formatted {} lines", lines.len());
+    }
+    println!("{}", numbers.bright_yellow());
+}
+
+fn compact_file(path: &PathBuf, limit: usize) {
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    match file.compact_file(path, limit) {
+        Ok((before, after)) if before == after => println!("File is already compact"),
+        Ok((before, after)) => println!(
+            "Compacted {} entry lines into {}",
+            before.to_string().bright_yellow(),
+            after.to_string().bright_yellow()
+        ),
+        Err(error) => fail(error),
+    }
+}
+
+fn expand_file(path: &PathBuf) {
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    match file.expand_file(path) {
+        Ok((before, after)) if before == after => println!("File is already expanded"),
+        Ok((before, after)) => println!(
+            "Expanded {} entry lines into {}",
+            before.to_string().bright_yellow(),
+            after.to_string().bright_yellow()
+        ),
+        Err(error) => fail(error),
+    }
+}
+
+fn export_entries(path: &PathBuf, format: ResolverFormat) {
+    let file = match HostsFile::read_from_file(path) {
+        Ok(file) => file,
+        Err(error) => fail(error),
+    };
+    for line in file.export(format) {
+        println!("{}", line);
+    }
+}
+
+fn import_entries(
+    path: &PathBuf,
+    config_path: &PathBuf,
+    format: ResolverFormat,
+    validation: Validation,
+    force: bool,
+) {
+    let (config_lines, _) = match HostsFile::read_text_from_file(config_path) {
+        Ok(config) => config,
+        Err(error) => fail(error),
+    };
+    let (entries, skipped, partial) =
+        match HostsFile::parse_resolver_config_with(format, &config_lines, validation) {
+            Ok(parsed) => parsed,
+            Err(error) => fail(error),
+        };
+    let (_lock, mut file) = match HostsFile::read_locked_with(path, validation) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    let operation = format!("import {} {}", format, config_path.display());
+    match file.add_all_to_file(path, &entries, &operation) {
+        Ok(actions) => {
+            let (added, updated, skipped_entries) = HostsFile::count_actions(&actions);
+            println!(
+                "Imported {} entries: {} added, {} updated, {} already existed",
+                entries.len().to_string().bright_yellow(),
+                added.to_string().bright_yellow(),
+                updated.to_string().bright_yellow(),
+                skipped_entries.to_string().bright_yellow()
+            );
+        }
+        Err(error) => fail(error),
+    }
+    if !skipped.is_empty() {
+        println!();
+        println!(
+            "{}",
+            format!(
+                "Skipped {} line(s) that do not map a hostname to an IPv4 address",
+                skipped.len()
+            )
+            .bright_white()
+            .on_bright_red()
+        );
+        println!(
+            "{}",
+            skipped
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+    if !partial.is_empty() {
+        eprintln!(
+            "{} line(s) {} also answer for every subdomain, which a hosts file cannot; only the names themselves were imported",
+            "Warning:".bright_red(),
+            partial
+                .iter()
+                .map(|line| line.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+    warn_unlisted(&file, force);
+}
+
+fn serve(path: &Path, address: &str) {
+    let mut server = match DnsServer::bind(address, path) {
+        Ok(server) => server,
+        Err(error) => fail(error),
+    };
+    match server.local_addr() {
+        Ok(address) => println!(
+            "Serving {} on {}",
+            path.display().to_string().bright_yellow(),
+            address.to_string().bright_yellow()
+        ),
+        Err(error) => fail(error),
+    }
+    if let Err(error) = server.run(|error| eprintln!("Error: {}", error)) {
+        fail(error);
+    }
+}
+
+fn remove_network(path: &PathBuf, network: Ipv4Cidr) {
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    match file.remove_network_from_file(path, &network) {
+        Ok(1) => println!(
+            "Entry within {} removed",
+            network.to_string().bright_yellow()
+        ),
+        Ok(amount) => println!(
+            "Removed {} entries within {}",
+            amount,
+            network.to_string().bright_yellow()
+        ),
+        Err(error) => fail(error),
+    }
+}
+
+fn select(entry: Option<String>, network: Option<Ipv4Cidr>) -> Selector {
+    match (entry, network) {
+        (_, Some(network)) => Selector::Network(network),
+        (Some(entry), None) => match entry.parse::<Selector>() {
+            Ok(selector) => selector,
+            Err(error) => fail(HostsError::Parse(error)),
+        },
+        (None, None) => fail(HostsError::Parse(String::from(
+            "give an IP, a hostname or --cidr",
+        ))),
+    }
+}
+
+fn use_profile(path: &PathBuf, name: &str, force: bool) {
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    match file.use_profile_in_file(path, name) {
+        Ok((0, 0)) => println!("Profile {} is already in use", name.bright_cyan()),
+        Ok((enabled, disabled)) => println!(
+            "Using profile {}: {} hostname(s) enabled, {} disabled",
+            name.bright_cyan(),
+            enabled.to_string().bright_yellow(),
+            disabled.to_string().bright_yellow()
+        ),
+        Err(error) => fail(error),
+    }
+    warn_unlisted(&file, force);
+}
+
+fn list_profiles(path: &PathBuf) {
+    let profiles = match HostsFile::read_from_file(path).and_then(|file| file.profiles()) {
+        Ok(profiles) => profiles,
+        Err(error) => fail(error),
+    };
+    if profiles.len() == 1 {
+        println!("Found 1 profile in {}", path.to_string_lossy());
+    } else {
+        println!(
+            "Found {} profiles in {}",
+            profiles.len(),
+            path.to_string_lossy()
+        );
+    }
+    let name_width = profiles
+        .iter()
+        .map(|profile| profile.name.len())
+        .max()
+        .unwrap_or(0);
+    for profile in profiles {
+        let state = match profile.state() {
+            ProfileState::Active => profile.state().to_string().bright_green(),
+            ProfileState::Inactive => profile.state().to_string().bright_black(),
+            ProfileState::Mixed => profile.state().to_string().bright_red(),
+        };
+        println!(
+            "{} {:8} {} hostname(s)",
+            format!("{:width$}", profile.name, width = name_width).bright_cyan(),
+            state,
+            profile.enabled + profile.disabled
+        );
+    }
+}
+
+fn toggle_entries(path: &PathBuf, selector: Selector, enable: bool, force: bool) {
+    let (_lock, mut file) = match HostsFile::read_locked(path) {
+        Ok(locked) => locked,
+        Err(error) => fail(error),
+    };
+    let (result, action) = if enable {
+        (file.enable_in_file(path, &selector), "enabled")
+    } else {
+        (file.disable_in_file(path, &selector), "disabled")
+    };
+    let selector = match selector {
+        Selector::Host(_) => selector.to_string().bright_cyan(),
+        Selector::Ip(_) => selector.to_string().bright_yellow(),
+        Selector::Network(_) => selector.to_string().bright_yellow(),
+    };
+    match result {
+        Ok(1) => println!("1 entry matching {} {}", selector, action),
+        Ok(amount) => println!("{} entries matching {} {}", amount, selector, action),
+        Err(error) => fail(error),
     }
+    warn_unlisted(&file, force);
 }
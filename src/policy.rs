@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+use fqdn::FQDN;
+
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+use crate::hostsfile::parse::ParsedLine;
+use crate::hostsfile::HostsFile;
+use crate::validation::Validation;
+
+/// A hostname pattern: either an exact name, or `*.domain` for the domain
+/// itself and every name below it. `*` alone matches every name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    pub domain: String,
+    pub wildcard: bool,
+}
+
+impl Pattern {
+    pub fn matches(&self, host: &str) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if self.wildcard && self.domain.is_empty() {
+            return true;
+        }
+        host == self.domain
+            || (self.wildcard
+                && host.ends_with(&self.domain)
+                && host[..host.len() - self.domain.len()].ends_with('.'))
+    }
+}
+
+impl std::str::FromStr for Pattern {
+    type Err = HostsError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "*" {
+            return Ok(Pattern {
+                domain: String::new(),
+                wildcard: true,
+            });
+        }
+        let (domain, wildcard) = match value.strip_prefix("*.") {
+            Some(domain) => (domain, true),
+            None => (value, false),
+        };
+        // Internationalized patterns match the punycode the entries hold.
+        let domain = HostsEntry::parse_host(domain)?
+            .to_string()
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        Ok(Pattern { domain, wildcard })
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.wildcard, self.domain.is_empty()) {
+            (true, true) => write!(f, "*"),
+            (true, false) => write!(f, "*.{}", self.domain),
+            (false, _) => write!(f, "{}", self.domain),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// `allow <pattern>`: names that may be added without `--force`.
+    Allow(Pattern),
+    /// `deny <pattern> [except <ip>...]`: names that may only point at the
+    /// listed IPs, such as a sink address, or nowhere at all.
+    Deny(Pattern, Vec<Ipv4Addr>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Allowed,
+    /// Not covered by any `allow` rule; added with a warning.
+    Unlisted(String),
+    /// Refused by a `deny` rule, even with `--force`.
+    Denied(String),
+}
+
+/// The rules every change is checked against: the names reserved for
+/// local use, plus the rules of a file next to the hosts file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+}
+
+impl Policy {
+    /// Names reserved for testing, loopback and private networks, which are
+    /// always allowed unless a `deny` rule says otherwise.
+    pub const DEFAULT_ALLOWED: [&'static str; 3] = ["*.test", "*.localhost", "*.internal"];
+
+    pub fn defaults() -> Self {
+        Policy {
+            rules: Policy::DEFAULT_ALLOWED
+                .iter()
+                .map(|pattern| {
+                    Rule::Allow(Pattern {
+                        domain: String::from(&pattern[2..]),
+                        wildcard: true,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    pub fn path_for(hosts_path: &Path) -> PathBuf {
+        let mut file_name = hosts_path.file_name().unwrap_or_default().to_os_string();
+        file_name.push(".policy");
+        hosts_path.with_file_name(file_name)
+    }
+
+    /// The default rules followed by those of the policy file, if there is
+    /// one.
+    pub fn read_for(hosts_path: &Path) -> Result<Self, HostsError> {
+        let mut policy = Policy::defaults();
+        match std::fs::read_to_string(Policy::path_for(hosts_path)) {
+            Ok(content) => policy.rules.append(&mut Policy::parse(&content)?.rules),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+            Err(error) => return Err(HostsError::from(error)),
+        }
+        Ok(policy)
+    }
+
+    pub fn parse(content: &str) -> Result<Self, HostsError> {
+        let mut rules = Vec::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = match line.split_once('#') {
+                Some((line, _)) => line.trim(),
+                None => line.trim(),
+            };
+            let invalid = || {
+                HostsError::Parse(format!(
+                    "invalid policy rule on line {}: {}",
+                    index + 1,
+                    line
+                ))
+            };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let rule = match words.as_slice() {
+                [] => continue,
+                ["allow", pattern] => Rule::Allow(pattern.parse()?),
+                ["deny", pattern] => Rule::Deny(pattern.parse()?, Vec::new()),
+                ["deny", pattern, "except", ips @ ..] if !ips.is_empty() => {
+                    let ips = ips
+                        .iter()
+                        .map(|ip| ip.parse::<Ipv4Addr>().map_err(|_| invalid()))
+                        .collect::<Result<Vec<Ipv4Addr>, HostsError>>()?;
+                    Rule::Deny(pattern.parse()?, ips)
+                }
+                _ => return Err(invalid()),
+            };
+            rules.push(rule);
+        }
+        Ok(Policy { rules })
+    }
+
+    /// Checks every hostname of `entry`. A `deny` rule wins over an `allow`
+    /// rule, and a name that points at one of the IPs a `deny` rule lets
+    /// through needs no `allow` rule.
+    pub fn check(&self, entry: &HostsEntry) -> Verdict {
+        let has_allow_rules = self.rules.iter().any(|rule| matches!(rule, Rule::Allow(_)));
+        let mut verdict = Verdict::Allowed;
+        for host in entry.hosts() {
+            let host = host.to_string();
+            let mut excepted = false;
+            for rule in self.rules.iter() {
+                match rule {
+                    Rule::Deny(pattern, ips) if pattern.matches(&host) => {
+                        if !ips.contains(&entry.ip) {
+                            return Verdict::Denied(match ips.as_slice() {
+                                [] => format!("{} is denied by the policy ({})", host, pattern),
+                                ips => format!(
+                                    "{} may only point at {} ({})",
+                                    host,
+                                    ips.iter()
+                                        .map(|ip| ip.to_string())
+                                        .collect::<Vec<String>>()
+                                        .join(", "),
+                                    pattern
+                                ),
+                            });
+                        }
+                        excepted = true;
+                    }
+                    Rule::Deny(_, _) => {}
+                    Rule::Allow(_) => {}
+                }
+            }
+            let allowed = self.rules.iter().any(|rule| match rule {
+                Rule::Allow(pattern) => pattern.matches(&host),
+                Rule::Deny(_, _) => false,
+            });
+            if has_allow_rules && !allowed && !excepted && verdict == Verdict::Allowed {
+                verdict =
+                    Verdict::Unlisted(format!("{} is outside the domains the policy allows", host));
+            }
+        }
+        verdict
+    }
+
+    /// Checks the entries and fails on the first denied one. Returns a
+    /// warning for each unlisted one.
+    pub fn enforce<'a, T>(&self, entries: T) -> Result<Vec<String>, HostsError>
+    where
+        T: IntoIterator<Item = &'a HostsEntry>,
+    {
+        let mut warnings = Vec::new();
+        for entry in entries {
+            match self.check(entry) {
+                Verdict::Allowed => {}
+                Verdict::Unlisted(reason) => warnings.push(reason),
+                Verdict::Denied(reason) => return Err(HostsError::Validation(reason)),
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// Enforces the policy on the mappings a change brings in: those among
+    /// the `after` lines that are not among the `before` lines. Mappings the
+    /// change leaves alone or only disables are not checked again.
+    pub fn review(&self, before: &[String], after: &[String]) -> Result<Vec<String>, HostsError> {
+        let mappings = |lines: &[String]| -> Vec<(Ipv4Addr, FQDN)> {
+            let mut mappings = Vec::new();
+            for line in lines {
+                if let ParsedLine::Entry(entry) =
+                    HostsFile::parse_line_with(line, Validation::Permissive)
+                {
+                    for host in entry.hosts() {
+                        mappings.push((entry.ip, host.clone()));
+                    }
+                }
+            }
+            mappings
+        };
+        let existing: HashSet<(Ipv4Addr, FQDN)> = mappings(before).into_iter().collect();
+        let added: Vec<HostsEntry> = mappings(after)
+            .into_iter()
+            .filter(|mapping| !existing.contains(mapping))
+            .map(|(ip, host)| HostsEntry::new(ip, host))
+            .collect();
+        self.enforce(added.iter())
+    }
+}
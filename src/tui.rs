@@ -0,0 +1,493 @@
+use std::io::Write;
+use std::panic::PanicHookInfo;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::{cursor, event, execute, queue, terminal};
+
+use crate::error::HostsError;
+use crate::hostsentry::HostsEntry;
+use crate::hostsfile::journal::JournalRecord;
+use crate::hostsfile::parse::ParsedLine;
+use crate::hostsfile::HostsFile;
+
+/// What the editor is doing with the keys it receives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Browse,
+    /// Incremental search; `origin` is where the cursor was when it began.
+    Search {
+        query: String,
+        origin: usize,
+    },
+    /// Editing the entry under the cursor, or a new one inserted after it.
+    Edit {
+        input: String,
+        new_line: bool,
+    },
+    /// Showing the pending changes before they are written.
+    Confirm(JournalRecord),
+}
+
+/// How a row of the screen is highlighted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowKind {
+    Entry,
+    Disabled,
+    Comment,
+    Invalid,
+    Empty,
+    Removed,
+    Added,
+    Header,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    pub kind: RowKind,
+    pub text: String,
+    pub selected: bool,
+}
+
+/// The state of `hosts tui`, kept apart from the terminal so every key can
+/// be driven and checked without one.
+pub struct Editor {
+    pub path: PathBuf,
+    pub file: HostsFile,
+    pub cursor: usize,
+    pub mode: Mode,
+    pub message: Option<String>,
+    saved: Vec<String>,
+    /// The file as it was on disk when it was opened or last saved, so a
+    /// save does not overwrite changes made by something else meanwhile.
+    on_disk: Vec<u8>,
+    offset: usize,
+    search: String,
+    quit: bool,
+}
+
+impl Editor {
+    pub fn open(path: &PathBuf) -> Result<Self, HostsError> {
+        let on_disk = std::fs::read(path)?;
+        let (lines, text_format) = HostsFile::decode(&on_disk)?;
+        let mut file = HostsFile::read_from_lines(lines.into_iter().map(Ok))?;
+        file.text_format = text_format;
+        Ok(Editor {
+            path: path.clone(),
+            saved: file.render(),
+            on_disk,
+            file,
+            cursor: 0,
+            mode: Mode::Browse,
+            message: None,
+            offset: 0,
+            search: String::new(),
+            quit: false,
+        })
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.file.render() != self.saved
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Result<(), HostsError> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return Ok(());
+        }
+        let quit_requested = self.message.as_deref() == Some(Editor::UNSAVED);
+        self.message = None;
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => self.browse(key, quit_requested),
+            Mode::Search { query, origin } => self.search(key, query, origin),
+            Mode::Edit { input, new_line } => self.edit(key, input, new_line),
+            Mode::Confirm(record) => self.confirm(key, record)?,
+        }
+        Ok(())
+    }
+
+    const UNSAVED: &'static str = "Unsaved changes, press q again to quit without saving";
+
+    fn browse(&mut self, key: KeyEvent, quit_requested: bool) {
+        let last = self.file.lines.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.cursor = (self.cursor + 1).min(last),
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(10),
+            KeyCode::PageDown => self.cursor = (self.cursor + 10).min(last),
+            KeyCode::Home | KeyCode::Char('g') => self.cursor = 0,
+            KeyCode::End | KeyCode::Char('G') => self.cursor = last,
+            KeyCode::Char('/') => {
+                self.mode = Mode::Search {
+                    query: String::new(),
+                    origin: self.cursor,
+                }
+            }
+            KeyCode::Char('n') => {
+                if let Some(index) = self.find(&self.search, self.cursor + 1) {
+                    self.cursor = index;
+                } else {
+                    self.message = Some(String::from("No more matches"));
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Char('t') => self.toggle(),
+            KeyCode::Enter | KeyCode::Char('e') => match self.file.lines.get(self.cursor) {
+                Some(ParsedLine::Entry(entry)) => {
+                    self.mode = Mode::Edit {
+                        input: entry.to_string(),
+                        new_line: false,
+                    }
+                }
+                _ => self.message = Some(String::from("Only entries can be edited")),
+            },
+            KeyCode::Char('a') => {
+                self.mode = Mode::Edit {
+                    input: String::new(),
+                    new_line: true,
+                }
+            }
+            KeyCode::Char('s') | KeyCode::Char('w') => {
+                if self.has_changes() {
+                    let after = self.file.render();
+                    self.mode =
+                        Mode::Confirm(JournalRecord::new("tui", "Edited", &self.saved, &after));
+                } else {
+                    self.message = Some(String::from("No changes to save"));
+                }
+            }
+            KeyCode::Char('q') | KeyCode::Esc => {
+                if self.has_changes() && !quit_requested {
+                    self.message = Some(String::from(Editor::UNSAVED));
+                } else {
+                    self.quit = true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn search(&mut self, key: KeyEvent, mut query: String, origin: usize) {
+        match key.code {
+            KeyCode::Esc => {
+                self.cursor = origin;
+                return;
+            }
+            KeyCode::Enter => {
+                self.search = query;
+                return;
+            }
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(c) => query.push(c),
+            _ => {}
+        }
+        match self.find(&query, origin) {
+            Some(index) => self.cursor = index,
+            None => {
+                self.cursor = origin;
+                self.message = Some(String::from("No match"));
+            }
+        }
+        self.mode = Mode::Search { query, origin };
+    }
+
+    fn edit(&mut self, key: KeyEvent, mut input: String, new_line: bool) {
+        match key.code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => match Editor::parse_input(&input) {
+                Ok(mut entry) => {
+                    if new_line {
+                        let index = (self.cursor + 1).min(self.file.lines.len());
+                        self.file.lines.insert(index, ParsedLine::Entry(entry));
+                        self.cursor = index;
+                    } else if let Some(ParsedLine::Entry(current)) =
+                        self.file.lines.get_mut(self.cursor)
+                    {
+                        entry.comment = current.comment.take();
+                        *current = entry;
+                    }
+                    return;
+                }
+                Err(error) => self.message = Some(error.to_string()),
+            },
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            _ => {}
+        }
+        self.mode = Mode::Edit { input, new_line };
+    }
+
+    fn confirm(&mut self, key: KeyEvent, record: JournalRecord) -> Result<(), HostsError> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Enter => {
+                let _lock = HostsFile::lock(&self.path)?;
+                if std::fs::read(&self.path)? != self.on_disk {
+                    return Err(HostsError::Conflict(format!(
+                        "{} changed on disk since it was opened, quit and reopen it to edit",
+                        self.path.display()
+                    )));
+                }
+                self.file
+                    .write_journaled(&self.path, "tui", "Edited", &self.saved)?;
+                self.saved = self.file.render();
+                self.on_disk = std::fs::read(&self.path)?;
+                self.message = Some(match self.file.warnings.first() {
+                    Some(warning) => format!("Saved {}, warning: {}", self.path.display(), warning),
+                    None => format!("Saved {}", self.path.display()),
+                });
+            }
+            KeyCode::Char('n') | KeyCode::Esc => {}
+            _ => self.mode = Mode::Confirm(record),
+        }
+        Ok(())
+    }
+
+    fn toggle(&mut self) {
+        let line = match self.file.lines.get_mut(self.cursor) {
+            Some(line) => line,
+            None => return,
+        };
+        let toggled = match &*line {
+            ParsedLine::Entry(entry) => Some(ParsedLine::disabled(entry)),
+            other => other.disabled_entry().map(ParsedLine::Entry),
+        };
+        match toggled {
+            Some(toggled) => *line = toggled,
+            None => self.message = Some(String::from("Only entries can be toggled")),
+        }
+    }
+
+    /// The first line from `start` on, wrapping around, whose text contains
+    /// `query`, ignoring case.
+    fn find(&self, query: &str, start: usize) -> Option<usize> {
+        if query.is_empty() || self.file.lines.is_empty() {
+            return None;
+        }
+        let query = query.to_lowercase();
+        let ip_width = HostsFile::ip_width_of(&self.file.lines);
+        let count = self.file.lines.len();
+        (0..count)
+            .map(|offset| (start + offset) % count)
+            .find(|&index| {
+                HostsFile::format_line(&self.file.lines[index], ip_width)
+                    .to_lowercase()
+                    .contains(&query)
+            })
+    }
+
+    /// Reads `ip host [alias...]`, checking every hostname against the IP
+    /// as `add` would.
+    pub fn parse_input(input: &str) -> Result<HostsEntry, HostsError> {
+        let mut fields = input.split_whitespace();
+        let (ip, host) = match (fields.next(), fields.next()) {
+            (Some(ip), Some(host)) => (ip, host),
+            _ => {
+                return Err(HostsError::Parse(String::from(
+                    "Give an IP and at least one hostname",
+                )))
+            }
+        };
+        let mut entry = HostsEntry::parse(ip, host)?;
+        for alias in fields {
+            let alias = HostsEntry::parse(ip, alias)?.host;
+            if !entry.has_host(&alias) {
+                entry.aliases.push(alias);
+            }
+        }
+        Ok(entry)
+    }
+
+    /// The rows to show in `height` lines, scrolled to keep the cursor in
+    /// sight. While confirming, these are the pending changes instead.
+    pub fn view(&mut self, height: usize) -> Vec<Row> {
+        if let Mode::Confirm(record) = &self.mode {
+            let mut rows = vec![Row {
+                kind: RowKind::Header,
+                text: format!("@@ line {}", record.start + 1),
+                selected: false,
+            }];
+            rows.extend(record.before.iter().map(|line| Row {
+                kind: RowKind::Removed,
+                text: format!("- {}", line),
+                selected: false,
+            }));
+            rows.extend(record.after.iter().map(|line| Row {
+                kind: RowKind::Added,
+                text: format!("+ {}", line),
+                selected: false,
+            }));
+            rows.truncate(height);
+            return rows;
+        }
+
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if height > 0 && self.cursor >= self.offset + height {
+            self.offset = self.cursor + 1 - height;
+        }
+        let ip_width = HostsFile::ip_width_of(&self.file.lines);
+        self.file
+            .lines
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(height)
+            .map(|(index, line)| Row {
+                kind: match line {
+                    ParsedLine::Entry(_) => RowKind::Entry,
+                    ParsedLine::Comment(_) if line.disabled_entry().is_some() => RowKind::Disabled,
+                    ParsedLine::Comment(_) => RowKind::Comment,
+                    ParsedLine::Invalid(_) => RowKind::Invalid,
+                    ParsedLine::Empty => RowKind::Empty,
+                },
+                text: HostsFile::format_line(line, ip_width),
+                selected: index == self.cursor,
+            })
+            .collect()
+    }
+
+    /// The bottom line: the input being typed, a message, or the keys.
+    pub fn status(&self) -> String {
+        match &self.mode {
+            Mode::Search { query, .. } => format!("/{}", query),
+            Mode::Edit { input, .. } => match &self.message {
+                Some(message) => format!("{} | edit: {}", message, input),
+                None => format!("edit: {}", input),
+            },
+            Mode::Confirm(_) => String::from("Save these changes? [y/n]"),
+            Mode::Browse => match &self.message {
+                Some(message) => message.clone(),
+                None => {
+                    let changed = if self.has_changes() {
+                        " [modified]"
+                    } else {
+                        ""
+                    };
+                    format!(
+                        "{}{} | / search  n next  space toggle  e edit  a add  s save  q quit",
+                        self.path.display(),
+                        changed
+                    )
+                }
+            },
+        }
+    }
+}
+
+/// Puts the terminal in raw mode on the alternate screen, and restores it
+/// when dropped, so it is restored however the editor ends, an error or a
+/// panic included.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter(stdout: &mut std::io::Stdout) -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(stdout, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Runs the editor full screen until it is quit.
+pub fn run(path: &PathBuf) -> Result<(), HostsError> {
+    let mut editor = Editor::open(path)?;
+    let mut stdout = std::io::stdout();
+    let guard = TerminalGuard::enter(&mut stdout)?;
+    // The previous hook prints the panic message right away, which would be
+    // lost on the alternate screen, so the terminal is restored first. It is
+    // put back once the editor is left.
+    let previous_hook: Arc<dyn Fn(&PanicHookInfo<'_>) + Send + Sync> =
+        Arc::from(std::panic::take_hook());
+    let hook = Arc::clone(&previous_hook);
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+        hook(info);
+    }));
+    let result = run_loop(&mut editor, &mut stdout);
+    drop(guard);
+    let _ = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| previous_hook(info)));
+    Ok(result?)
+}
+
+fn run_loop(editor: &mut Editor, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+    while !editor.should_quit() {
+        draw(editor, stdout)?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Release {
+                if let Err(error) = editor.handle_key(key) {
+                    editor.message = Some(format!("Error: {}", error));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn draw(editor: &mut Editor, stdout: &mut std::io::Stdout) -> std::io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let width = width as usize;
+    let rows = editor.view((height as usize).saturating_sub(1));
+    queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+    for (index, row) in rows.iter().enumerate() {
+        let color = match row.kind {
+            RowKind::Entry => Color::Yellow,
+            RowKind::Disabled => Color::DarkGrey,
+            RowKind::Comment => Color::Grey,
+            RowKind::Invalid => Color::Red,
+            RowKind::Empty => Color::Reset,
+            RowKind::Removed => Color::Red,
+            RowKind::Added => Color::Green,
+            RowKind::Header => Color::Cyan,
+        };
+        queue!(
+            stdout,
+            cursor::MoveTo(0, index as u16),
+            SetForegroundColor(color)
+        )?;
+        if row.selected {
+            queue!(stdout, SetAttribute(Attribute::Reverse))?;
+        }
+        let text: String = row.text.chars().take(width).collect();
+        queue!(
+            stdout,
+            Print(text),
+            SetAttribute(Attribute::Reset),
+            ResetColor
+        )?;
+    }
+    let status: String = editor.status().chars().take(width).collect();
+    queue!(
+        stdout,
+        cursor::MoveTo(0, height.saturating_sub(1)),
+        SetAttribute(Attribute::Reverse),
+        Print(format!("{:width$}", status, width = width)),
+        SetAttribute(Attribute::Reset)
+    )?;
+    stdout.flush()
+}
@@ -0,0 +1,97 @@
+/// How strictly hostnames are checked, both when the hosts file is read and
+/// when entries are added. Every mode accepts internationalized names, which
+/// are checked in their punycode form.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Validation {
+    /// Fully qualified RFC 1123 names: at least two labels of letters,
+    /// digits and inner hyphens, 63 characters at most each, and a
+    /// top-level label that is not all digits.
+    Strict,
+    /// What resolvers look up from a hosts file: single labels such as
+    /// `localhost` and underscores such as `_dmarc.test` are fine, but the
+    /// DNS length limits still apply.
+    #[default]
+    Resolver,
+    /// Any name that can be stored, including labels longer than 63
+    /// characters as found in some blocklists.
+    Permissive,
+}
+
+impl Validation {
+    pub const MAX_LABEL_LENGTH: usize = 63;
+    pub const MAX_NAME_LENGTH: usize = 253;
+
+    /// Checks an ASCII hostname and says why it is rejected, if it is.
+    pub fn check(&self, host: &str) -> Result<(), String> {
+        if *self == Validation::Permissive {
+            return Ok(());
+        }
+        let host = host.trim_end_matches('.');
+        if host.len() > Validation::MAX_NAME_LENGTH {
+            return Err(format!(
+                "name is {} characters long, the limit is {}",
+                host.len(),
+                Validation::MAX_NAME_LENGTH
+            ));
+        }
+        let labels: Vec<&str> = host.split('.').collect();
+        for label in labels.iter() {
+            if label.len() > Validation::MAX_LABEL_LENGTH {
+                return Err(format!(
+                    "label {} is {} characters long, the limit is {}",
+                    label,
+                    label.len(),
+                    Validation::MAX_LABEL_LENGTH
+                ));
+            }
+        }
+        if *self == Validation::Resolver {
+            return Ok(());
+        }
+        if labels.len() < 2 {
+            return Err(String::from(
+                "single-label name, strict names need a domain such as .test",
+            ));
+        }
+        for label in labels.iter() {
+            if label.contains('_') {
+                return Err(format!("label {} contains an underscore", label));
+            }
+            if label.starts_with('-') || label.ends_with('-') {
+                return Err(format!("label {} starts or ends with a hyphen", label));
+            }
+        }
+        match labels.last() {
+            Some(top) if top.chars().all(|c| c.is_ascii_digit()) => {
+                Err(format!("top-level label {} is all digits", top))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::str::FromStr for Validation {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "strict" => Ok(Validation::Strict),
+            "resolver" => Ok(Validation::Resolver),
+            "permissive" => Ok(Validation::Permissive),
+            _ => Err(format!(
+                "unknown validation mode {}, expected strict, resolver or permissive",
+                value
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Validation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Validation::Strict => write!(f, "strict"),
+            Validation::Resolver => write!(f, "resolver"),
+            Validation::Permissive => write!(f, "permissive"),
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use hosts::audit::{format_time, parse_time, Audit, AuditFilter, AuditRecord};
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::journal::{snapshot_hash, JournalRecord};
+use hosts::hostsfile::HostsFile;
+
+mod common;
+
+const HOSTS: &str = "# comment\n1.1.1.1 first.domain.com\n";
+
+fn get_test_record(time: u64, before: &[&str], after: &[&str]) -> AuditRecord {
+    let lines = |lines: &[&str]| lines.iter().map(|line| String::from(*line)).collect();
+    AuditRecord {
+        time,
+        user: Some(String::from("alice")),
+        claimed_user: Some(String::from("alice \"admin\"")),
+        uid: Some(1000),
+        command: vec![String::from("hosts"), String::from("add")],
+        operation: String::from("add"),
+        before: lines(before),
+        after: lines(after),
+        before_hash: snapshot_hash(&[]),
+        after_hash: 0,
+    }
+}
+
+#[test]
+fn it_round_trips_records_through_json() {
+    let record = get_test_record(1700000000, &["1.1.1.1 a.test\t# old\\"], &[]);
+    let json = record.to_json();
+    assert!(!json.contains('\n'));
+    assert!(json.contains("\"after_hash\":\"0000000000000000\""));
+    assert_eq!(AuditRecord::from_json(&json).unwrap(), record);
+
+    let anonymous = AuditRecord {
+        user: None,
+        claimed_user: None,
+        uid: None,
+        ..record
+    };
+    assert!(anonymous
+        .to_json()
+        .contains("\"user\":null,\"claimed_user\":null,\"uid\":null"));
+    assert_eq!(
+        AuditRecord::from_json(&anonymous.to_json()).unwrap(),
+        anonymous
+    );
+    assert!(AuditRecord::from_json("{\"time\":1}").is_err());
+}
+
+#[test]
+fn it_parses_and_formats_times() {
+    assert_eq!(parse_time("1700000000").unwrap(), 1700000000);
+    assert_eq!(parse_time("1970-01-01").unwrap(), 0);
+    assert_eq!(parse_time("2024-02-29T12:30:15").unwrap(), 1709209815);
+    assert_eq!(format_time(1709209815), "2024-02-29 12:30:15");
+    for invalid in ["yesterday", "2024-13-01", "2024-01-01T25:00:00", "2024-01"] {
+        assert!(parse_time(invalid).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn it_filters_records_by_host_and_time() {
+    let record = get_test_record(
+        100,
+        &["1.1.1.1 a.test"],
+        &["# 1.1.1.1 a.test", "2.2.2.2 b.test"],
+    );
+    let filter = |host: Option<&str>, since: Option<u64>, until: Option<u64>| {
+        AuditFilter {
+            host: host.map(String::from),
+            since,
+            until,
+        }
+        .matches(&record)
+    };
+    assert!(filter(None, None, None));
+    assert!(filter(Some("A.test"), Some(100), Some(100)));
+    assert!(filter(Some("b.test"), None, None));
+    assert!(!filter(Some("c.test"), None, None));
+    assert!(!filter(None, Some(101), None));
+    assert!(!filter(None, None, Some(99)));
+}
+
+#[test]
+fn it_audits_changes_and_undo() {
+    let path = common::temp_hosts(HOSTS);
+    let entry = HostsEntry::from((2, 2, 2, 2), "second.domain.com");
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.add_to_file(&path, &entry).unwrap();
+    HostsFile::undo(&path, 1).unwrap();
+
+    let (records, skipped) = Audit::read(&path, &AuditFilter::default()).unwrap();
+    assert!(skipped.is_empty());
+    let operations: Vec<&str> = records
+        .iter()
+        .map(|record| record.operation.as_str())
+        .collect();
+    assert_eq!(
+        operations,
+        vec![
+            "add 2.2.2.2 second.domain.com",
+            "undo add 2.2.2.2 second.domain.com"
+        ]
+    );
+    assert_eq!(records[0].after, records[1].before);
+    assert_eq!(records[0].before_hash, records[1].after_hash);
+    assert!(!records[0].command.is_empty());
+
+    let filter = AuditFilter {
+        host: Some(String::from("first.domain.com")),
+        ..AuditFilter::default()
+    };
+    assert!(Audit::read(&path, &filter).unwrap().0.is_empty());
+}
+
+#[test]
+fn it_skips_lines_that_are_not_records() {
+    let path = common::temp_hosts(HOSTS);
+    let entry = HostsEntry::from((2, 2, 2, 2), "second.domain.com");
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.add_to_file(&path, &entry).unwrap();
+    let audit_path = Audit::path_for(&path);
+    let mut content = std::fs::read_to_string(&audit_path).unwrap();
+    let truncated = content[..content.len() / 2].to_string();
+    content.push_str(&truncated);
+    content.push_str("\nnot json\n");
+    std::fs::write(&audit_path, content).unwrap();
+
+    let (records, skipped) = Audit::read(&path, &AuditFilter::default()).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(skipped, vec![2, 3]);
+}
+
+#[cfg(unix)]
+#[test]
+fn it_names_the_user_after_the_uid() {
+    let record = AuditRecord::new("add", &JournalRecord::new("add", "", &[], &[]));
+    assert!(record.uid.is_some());
+    assert!(record.user.is_some());
+}
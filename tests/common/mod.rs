@@ -0,0 +1,32 @@
+use std::path::{Path, PathBuf};
+
+use tempfile::TempDir;
+
+/// A hosts file in a directory of its own, which is deleted along with the
+/// journal, policy and audit files next to it when this is dropped.
+pub struct TempHosts {
+    path: PathBuf,
+    _dir: TempDir,
+}
+
+impl std::ops::Deref for TempHosts {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempHosts {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Writes `content` to a new hosts file.
+pub fn temp_hosts(content: impl AsRef<[u8]>) -> TempHosts {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hosts");
+    std::fs::write(&path, content).unwrap();
+    TempHosts { path, _dir: dir }
+}
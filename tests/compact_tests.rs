@@ -0,0 +1,136 @@
+use fqdn::fqdn;
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::add::AddResult;
+use hosts::hostsfile::parse::ParsedLine;
+use hosts::hostsfile::remove::RemoveResult;
+use hosts::hostsfile::HostsFile;
+
+fn get_alias_entry(ip: (u8, u8, u8, u8), hosts: &[&str]) -> ParsedLine {
+    let mut entry = HostsEntry::from(ip, hosts[0]);
+    entry.aliases = hosts[1..].iter().map(|host| fqdn!(host)).collect();
+    ParsedLine::Entry(entry)
+}
+
+fn get_test_lines() -> Vec<ParsedLine> {
+    vec![
+        ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "a.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((2, 2, 2, 2), "b.domain.com")),
+        ParsedLine::Comment(String::from("# comment")),
+        get_alias_entry((1, 1, 1, 1), &["c.domain.com", "a.domain.com"]),
+        ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "d.domain.com")),
+    ]
+}
+
+#[test]
+fn it_compacts_hosts_sharing_an_ip() {
+    let mut lines = get_test_lines();
+    HostsFile::compact_lines(&mut lines, HostsFile::ALIAS_LIMIT);
+    let expected = vec![
+        ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "a.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((2, 2, 2, 2), "b.domain.com")),
+        ParsedLine::Comment(String::from("# comment")),
+        get_alias_entry(
+            (1, 1, 1, 1),
+            &["c.domain.com", "a.domain.com", "d.domain.com"],
+        ),
+    ];
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn it_compacts_up_to_the_limit() {
+    let mut lines = get_test_lines();
+    HostsFile::compact_lines(&mut lines, 2);
+    let expected = vec![
+        ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "a.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((2, 2, 2, 2), "b.domain.com")),
+        ParsedLine::Comment(String::from("# comment")),
+        get_alias_entry((1, 1, 1, 1), &["c.domain.com", "a.domain.com"]),
+        get_alias_entry((1, 1, 1, 1), &["d.domain.com"]),
+    ];
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn it_keeps_entries_in_their_profile_when_compacting() {
+    let text = [
+        "1.1.1.1 a.domain.com",
+        "# profile staging",
+        "1.1.1.1 b.domain.com",
+        "1.1.1.1 c.domain.com",
+        "# end profile staging",
+        "1.1.1.1 d.domain.com",
+    ];
+    let mut file =
+        HostsFile::read_from_lines(text.iter().map(|line| Ok(String::from(*line)))).unwrap();
+    HostsFile::compact_lines(&mut file.lines, HostsFile::ALIAS_LIMIT);
+    assert_eq!(
+        file.render(),
+        vec![
+            "1.1.1.1 a.domain.com",
+            "# profile staging",
+            "1.1.1.1 b.domain.com c.domain.com",
+            "# end profile staging",
+            "1.1.1.1 d.domain.com",
+        ]
+    );
+}
+
+#[test]
+fn it_leaves_commented_entries_alone_when_compacting() {
+    let mut commented = HostsEntry::from((1, 1, 1, 1), "e.domain.com");
+    commented.comment = Some(String::from("# keep"));
+    let mut lines = get_test_lines();
+    lines.push(ParsedLine::Entry(commented.clone()));
+    HostsFile::compact_lines(&mut lines, HostsFile::ALIAS_LIMIT);
+    assert_eq!(lines.last(), Some(&ParsedLine::Entry(commented)));
+}
+
+#[test]
+fn it_expands_aliases() {
+    let mut lines = vec![get_alias_entry(
+        (1, 1, 1, 1),
+        &["c.domain.com", "a.domain.com"],
+    )];
+    HostsFile::expand_lines(&mut lines);
+    let expected = vec![
+        ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "c.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "a.domain.com")),
+    ];
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn it_splits_an_alias_off_when_updating() {
+    let mut lines = vec![get_alias_entry(
+        (1, 1, 1, 1),
+        &["c.domain.com", "a.domain.com"],
+    )];
+    let entry = HostsEntry::from((2, 2, 2, 2), "a.domain.com");
+    match HostsFile::add_to_lines(&mut lines, &entry) {
+        AddResult::Updated(_) => {}
+        result => panic!("{:?} instead of updated", result),
+    }
+    let expected = vec![
+        get_alias_entry((1, 1, 1, 1), &["c.domain.com"]),
+        ParsedLine::Entry(entry),
+    ];
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn it_removes_an_alias_by_host() {
+    let mut lines = vec![get_alias_entry(
+        (1, 1, 1, 1),
+        &["c.domain.com", "a.domain.com"],
+    )];
+    match HostsFile::remove_from_lines(&mut lines, "c.domain.com") {
+        Ok(RemoveResult::RemovedByHost(1)) => {}
+        result => panic!("{:?} instead of 1 host removed", result),
+    }
+    let expected = vec![ParsedLine::Entry(HostsEntry::from(
+        (1, 1, 1, 1),
+        "a.domain.com",
+    ))];
+    assert_eq!(lines, expected);
+}
@@ -0,0 +1,119 @@
+use hosts::completions::{commands, complete, script, Shell};
+
+mod common;
+
+const HOSTS: &str = "1.1.1.1 a.domain.com b.domain.com\n# 2.2.2.2 c.domain.com\n";
+
+fn words(words: &[&str]) -> Vec<String> {
+    words.iter().map(|word| String::from(*word)).collect()
+}
+
+#[test]
+fn it_completes_subcommands_and_options() {
+    assert_eq!(
+        complete(&words(&["re"])),
+        vec!["resolve", "remove", "rename", "retarget", "render", "redo"]
+    );
+    assert_eq!(complete(&words(&["fmt", "--s"])), vec!["--sort"]);
+    assert_eq!(complete(&words(&["export", "--to", "c"])), vec!["coredns"]);
+    assert!(complete(&words(&["remove", "--path", ""])).is_empty());
+    assert_eq!(complete(&words(&["completions", "z"])), vec!["zsh"]);
+}
+
+#[test]
+fn it_completes_from_the_file() {
+    let path = common::temp_hosts(HOSTS);
+    let path = path.to_str().unwrap();
+    assert_eq!(
+        complete(&words(&["remove", "--path", path, ""])),
+        vec!["1.1.1.1", "a.domain.com", "b.domain.com"]
+    );
+    assert_eq!(
+        complete(&words(&["--path", path, "rename", "b"])),
+        vec!["b.domain.com"]
+    );
+    assert_eq!(
+        complete(&words(&["enable", "--path", path, ""])),
+        vec!["2.2.2.2", "c.domain.com"]
+    );
+    assert_eq!(
+        complete(&words(&["resolve", "--path", path, ""])),
+        vec!["a.domain.com", "b.domain.com"]
+    );
+}
+
+#[test]
+fn it_generates_scripts_calling_the_completer() {
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+        assert!(script(shell).contains("hosts __complete"));
+    }
+    assert!("powershell".parse::<Shell>().is_err());
+}
+
+/// The names listed under `heading` in the help of `hosts <command>`.
+fn help_section(command: &[&str], heading: &str) -> Vec<String> {
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hosts"))
+        .args(command)
+        .arg("--help")
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .skip_while(|line| *line != heading)
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .filter(|line| !line.starts_with("   "))
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|name| *name != "--help")
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn it_knows_every_subcommand_and_option_of_the_binary() {
+    let mut names: Vec<&str> = commands().map(|command| command.name).collect();
+    names.sort();
+    let mut subcommands = help_section(&[], "Commands:");
+    subcommands.sort();
+    assert_eq!(subcommands, names);
+    for command in commands() {
+        let mut options = help_section(&[command.name], "Options:");
+        for subcommand in help_section(&[command.name], "Commands:") {
+            options.extend(help_section(&[command.name, &subcommand], "Options:"));
+        }
+        options.sort();
+        options.dedup();
+        let mut expected: Vec<String> = command.options.iter().map(|o| o.to_string()).collect();
+        expected.sort();
+        assert_eq!(options, expected, "{}", command.name);
+    }
+}
+
+#[test]
+fn it_reads_the_top_level_path_like_the_binary() {
+    let path = common::temp_hosts(HOSTS);
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hosts"))
+        .args(["--path", path.to_str().unwrap(), "who", "1.1.1.1"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("a.domain.com"));
+}
+
+#[test]
+fn it_resolves_hostnames_from_the_file() {
+    let path = common::temp_hosts(HOSTS);
+    let resolve = |host: &str| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_hosts"))
+            .args(["--path", path.to_str().unwrap(), "resolve", host])
+            .output()
+            .unwrap()
+    };
+    let output = resolve("b.domain.com");
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("1.1.1.1"));
+    assert_eq!(resolve("c.domain.com").status.code(), Some(4));
+}
@@ -0,0 +1,56 @@
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::parse::ParsedLine;
+use hosts::hostsfile::query::Selector;
+use hosts::hostsfile::HostsFile;
+
+fn get_test_lines() -> Vec<ParsedLine> {
+    vec![
+        ParsedLine::Entry(HostsEntry::from((10, 1, 1, 1), "a.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((10, 2, 2, 2), "b.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((192, 168, 1, 1), "c.domain.com")),
+    ]
+}
+
+#[test]
+fn it_disables_entries_in_a_network() {
+    let mut lines = get_test_lines();
+    let selector = "10.0.0.0/8".parse::<Selector>().unwrap();
+    assert_eq!(HostsFile::disable_in_lines(&mut lines, &selector), 2);
+    let expected = vec![
+        ParsedLine::Comment(String::from("# 10.1.1.1 a.domain.com")),
+        ParsedLine::Comment(String::from("# 10.2.2.2 b.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((192, 168, 1, 1), "c.domain.com")),
+    ];
+    assert_eq!(lines, expected);
+}
+
+#[test]
+fn it_enables_disabled_entries() {
+    let mut lines = get_test_lines();
+    let selector = "10.0.0.0/8".parse::<Selector>().unwrap();
+    HostsFile::disable_in_lines(&mut lines, &selector);
+    let host = "b.domain.com".parse::<Selector>().unwrap();
+    assert_eq!(HostsFile::enable_in_lines(&mut lines, &host), 1);
+    assert_eq!(lines[1], get_test_lines()[1]);
+    assert_eq!(
+        lines[0].disabled_entry(),
+        Some(HostsEntry::from((10, 1, 1, 1), "a.domain.com"))
+    );
+}
+
+#[test]
+fn it_removes_entries_in_a_network() {
+    let mut lines = get_test_lines();
+    let network = "10.2.0.0/16".parse().unwrap();
+    assert_eq!(
+        HostsFile::remove_network_from_lines(&mut lines, &network),
+        1
+    );
+    assert_eq!(lines.len(), 2);
+}
+
+#[test]
+fn it_does_not_treat_plain_comments_as_disabled() {
+    let line = ParsedLine::Comment(String::from("# For example:"));
+    assert_eq!(line.disabled_entry(), None);
+}
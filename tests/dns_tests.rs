@@ -0,0 +1,96 @@
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use hosts::dns::DnsServer;
+use hosts::hostsfile::HostsFile;
+
+mod common;
+
+fn query(name: &str, record_type: u16) -> Vec<u8> {
+    let mut query = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+    for label in name.split('.') {
+        query.push(label.len() as u8);
+        query.extend_from_slice(label.as_bytes());
+    }
+    query.push(0);
+    query.extend_from_slice(&record_type.to_be_bytes());
+    query.extend_from_slice(&[0, 1]);
+    query
+}
+
+fn response_code(response: &[u8]) -> u8 {
+    response[3] & 0x0F
+}
+
+fn answer_count(response: &[u8]) -> u16 {
+    u16::from_be_bytes([response[6], response[7]])
+}
+
+fn get_file() -> HostsFile {
+    let lines = vec![
+        "1.1.1.1 a.domain.com b.domain.com",
+        "2.2.2.2 c.domain.com",
+        "# 3.3.3.3 disabled.domain.com",
+    ];
+    HostsFile::read_from_lines(lines.into_iter().map(|line| Ok(String::from(line)))).unwrap()
+}
+
+#[test]
+fn it_answers_a_queries() {
+    let file = get_file();
+    let response = DnsServer::respond(&file, &query("B.domain.com", 1)).unwrap();
+    assert_eq!(&response[0..2], &[0x12, 0x34]);
+    assert_eq!(response_code(&response), 0);
+    assert_eq!(answer_count(&response), 1);
+    assert_eq!(&response[response.len() - 4..], &[1, 1, 1, 1]);
+}
+
+#[test]
+fn it_answers_ptr_queries() {
+    let file = get_file();
+    let response = DnsServer::respond(&file, &query("1.1.1.1.in-addr.arpa", 12)).unwrap();
+    assert_eq!(response_code(&response), 0);
+    assert_eq!(answer_count(&response), 2);
+    let response = DnsServer::respond(&file, &query("3.3.3.3.in-addr.arpa", 12)).unwrap();
+    assert_eq!(response_code(&response), 3);
+}
+
+#[test]
+fn it_rejects_unknown_names_and_types() {
+    let file = get_file();
+    let response = DnsServer::respond(&file, &query("disabled.domain.com", 1)).unwrap();
+    assert_eq!(response_code(&response), 3);
+    let response = DnsServer::respond(&file, &query("c.domain.com", 28)).unwrap();
+    assert_eq!(response_code(&response), 0);
+    assert_eq!(answer_count(&response), 0);
+    let response = DnsServer::respond(&file, &query("c.domain.com", 15)).unwrap();
+    assert_eq!(response_code(&response), 5);
+}
+
+#[test]
+fn it_serves_over_udp_and_reloads() {
+    let path = common::temp_hosts("1.1.1.1 a.domain.com\n");
+    let mut server = DnsServer::bind("127.0.0.1:0", &path).unwrap();
+    let address = server.local_addr().unwrap();
+    std::thread::spawn(move || server.run(|error| panic!("{}", error)));
+
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let mut buffer = [0u8; 512];
+    client.send_to(&query("a.domain.com", 1), address).unwrap();
+    let length = client.recv(&mut buffer).unwrap();
+    assert_eq!(&buffer[length - 4..length], &[1, 1, 1, 1]);
+
+    std::fs::write(&path, "2.2.2.2 a.domain.com b.domain.com\n").unwrap();
+    client.send_to(&query("a.domain.com", 1), address).unwrap();
+    let length = client.recv(&mut buffer).unwrap();
+    assert_eq!(&buffer[length - 4..length], &[2, 2, 2, 2]);
+
+    // Same length, written right away: only the content tells it apart.
+    std::fs::write(&path, "3.3.3.3 a.domain.com b.domain.com\n").unwrap();
+    client.send_to(&query("a.domain.com", 1), address).unwrap();
+    let length = client.recv(&mut buffer).unwrap();
+    assert_eq!(&buffer[length - 4..length], &[3, 3, 3, 3]);
+}
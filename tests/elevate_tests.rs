@@ -0,0 +1,61 @@
+use hosts::hostsfile::elevate::Elevation;
+use hosts::hostsfile::HostsFile;
+
+mod common;
+
+const HOSTS: &str = "# comment\n";
+
+#[test]
+fn it_parses_the_elevation_command() {
+    assert_eq!(
+        Elevation::parse("sudo -n"),
+        Some(Elevation {
+            program: String::from("sudo"),
+            args: vec![String::from("-n")],
+        })
+    );
+    assert_eq!(Elevation::parse("  "), None);
+}
+
+#[test]
+fn it_pipes_the_content_through_the_elevation_command() {
+    let path = common::temp_hosts(HOSTS);
+    // `env` runs `tee` as it is, standing in for sudo.
+    let elevation = Elevation::parse("env").unwrap();
+    elevation.write(&path, b"1.1.1.1 a.domain.com\n").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "1.1.1.1 a.domain.com\n"
+    );
+}
+
+#[test]
+fn it_fails_when_the_elevation_command_fails() {
+    let path = common::temp_hosts(HOSTS);
+    let elevation = Elevation::parse("false").unwrap();
+    let error = elevation
+        .write(&path, b"1.1.1.1 a.domain.com\n")
+        .unwrap_err();
+    assert_eq!(error.exit_code(), 6);
+    // More than a pipe holds, so the command exits before it is all written.
+    let content = "1.1.1.1 a.domain.com\n".repeat(1 << 16);
+    for append in [false, true] {
+        let result = match append {
+            false => elevation.write(&path, content.as_bytes()),
+            true => elevation.append(&path, content.as_bytes()),
+        };
+        assert_eq!(result.unwrap_err().exit_code(), 6);
+    }
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "# comment\n");
+}
+
+#[test]
+fn it_only_elevates_when_the_write_is_denied() {
+    let path = common::temp_hosts(HOSTS);
+    let elevation = Elevation::parse("false").unwrap();
+    HostsFile::write_bytes(&path, b"2.2.2.2 b.domain.com\n", Some(&elevation)).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "2.2.2.2 b.domain.com\n"
+    );
+}
@@ -0,0 +1,80 @@
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::encoding::{Encoding, LineEnding};
+use hosts::hostsfile::parse::ParsedLine;
+use hosts::hostsfile::HostsFile;
+
+mod common;
+
+fn to_utf16le(content: &str) -> Vec<u8> {
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(content.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+    bytes
+}
+
+#[test]
+fn it_detects_crlf_and_bom() {
+    let (lines, format) =
+        HostsFile::decode(b"\xEF\xBB\xBF# comment\r\n1.1.1.1 a.domain.com\r\n").unwrap();
+    assert_eq!(lines, vec!["# comment", "1.1.1.1 a.domain.com"]);
+    assert_eq!(format.encoding, Encoding::Utf8);
+    assert!(format.bom);
+    assert_eq!(format.line_ending, LineEnding::CrLf);
+    assert!(format.final_newline);
+    assert!(format.mixed_line_endings.is_empty());
+}
+
+#[test]
+fn it_reports_mixed_line_endings() {
+    let (_, format) = HostsFile::decode(b"a\r\nb\nc\r\nd").unwrap();
+    assert_eq!(format.line_ending, LineEnding::CrLf);
+    assert_eq!(format.mixed_line_endings, vec![2]);
+    assert!(!format.final_newline);
+}
+
+#[test]
+fn it_round_trips_every_format() {
+    let contents: Vec<Vec<u8>> = vec![
+        b"# comment\r\n1.1.1.1 a.domain.com\r\n".to_vec(),
+        b"\xEF\xBB\xBF# comment\n1.1.1.1 a.domain.com".to_vec(),
+        to_utf16le("# comment\r\n1.1.1.1 a.domain.com\r\n"),
+    ];
+    for content in contents {
+        let (lines, format) = HostsFile::decode(&content).unwrap();
+        assert_eq!(HostsFile::encode(&lines, &format), content);
+    }
+}
+
+#[test]
+fn it_keeps_the_format_when_writing() {
+    let content = to_utf16le("# comment\r\n1.1.1.1 a.domain.com\r\n");
+    let path = common::temp_hosts(&content);
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    assert_eq!(file.text_format.encoding, Encoding::Utf16Le);
+    assert_eq!(file.entries_count(), 1);
+    file.add_to_file(&path, &HostsEntry::from((2, 2, 2, 2), "b.domain.com"))
+        .unwrap();
+    let expected = to_utf16le("# comment\r\n1.1.1.1 a.domain.com\r\n2.2.2.2 b.domain.com\r\n");
+    assert_eq!(std::fs::read(&path).unwrap(), expected);
+}
+
+#[test]
+fn it_streams_files_in_every_format() {
+    let contents: Vec<Vec<u8>> = vec![
+        b"\xEF\xBB\xBF# comment\r\n1.1.1.1 a.domain.com\r\n".to_vec(),
+        to_utf16le("# comment\r\n1.1.1.1 a.domain.com\r\n"),
+    ];
+    for content in contents {
+        let path = common::temp_hosts(content);
+        let lines: Vec<ParsedLine> = HostsFile::stream_from_file(&path)
+            .unwrap()
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            lines,
+            vec![
+                ParsedLine::Comment(String::from("# comment")),
+                ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "a.domain.com")),
+            ]
+        );
+    }
+}
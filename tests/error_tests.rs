@@ -0,0 +1,99 @@
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use hosts::error::HostsError;
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::HostsFile;
+
+mod common;
+
+const HOSTS: &str = "# comment\n1.1.1.1 a.domain.com\n";
+
+#[test]
+fn it_gives_each_kind_its_own_exit_code() {
+    let errors = [
+        HostsError::Parse(String::new()),
+        HostsError::Validation(String::new()),
+        HostsError::NotFound(String::new()),
+        HostsError::Conflict(String::new()),
+        HostsError::PermissionDenied(ErrorKind::PermissionDenied.into()),
+        HostsError::Lock(String::new()),
+        HostsError::Io(ErrorKind::Other.into()),
+    ];
+    let codes: Vec<i32> = errors.iter().map(HostsError::exit_code).collect();
+    assert_eq!(codes, vec![2, 3, 4, 5, 6, 7, 8]);
+}
+
+#[test]
+fn it_tells_permission_errors_apart() {
+    let error = HostsError::from(std::io::Error::from(ErrorKind::PermissionDenied));
+    assert!(matches!(error, HostsError::PermissionDenied(_)));
+    let error = HostsError::from(std::io::Error::from(ErrorKind::NotFound));
+    assert!(matches!(error, HostsError::Io(_)));
+}
+
+#[test]
+fn it_fails_to_remove_what_is_not_there() {
+    let path = common::temp_hosts(HOSTS);
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    let result = file.remove_from_file(&path, "b.domain.com");
+    assert!(matches!(result, Err(HostsError::NotFound(_))));
+    let result = file.remove_from_file(&path, "!");
+    assert!(matches!(result, Err(HostsError::Parse(_))));
+    assert!(HostsFile::history(&path).unwrap().is_empty());
+}
+
+#[test]
+fn it_fails_when_no_entry_matches_a_selector() {
+    let path = common::temp_hosts(HOSTS);
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    let result = file.remove_network_from_file(&path, &"10.0.0.0/8".parse().unwrap());
+    assert!(matches!(result, Err(HostsError::NotFound(_))));
+    let result = file.disable_in_file(&path, &"b.domain.com".parse().unwrap());
+    assert!(matches!(result, Err(HostsError::NotFound(_))));
+    let result = file.enable_in_file(&path, &"a.domain.com".parse().unwrap());
+    assert!(matches!(result, Err(HostsError::NotFound(_))));
+    assert!(HostsFile::history(&path).unwrap().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn it_fails_while_another_change_holds_the_lock() {
+    let path = common::temp_hosts(HOSTS);
+    let lock = HostsFile::lock(&path).unwrap();
+    let result = HostsFile::lock_within(&path, Duration::from_millis(50));
+    assert_eq!(result.unwrap_err().exit_code(), 7);
+    drop(lock);
+    HostsFile::lock_within(&path, Duration::from_millis(50)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn it_waits_for_another_change_to_finish() {
+    let path = common::temp_hosts(HOSTS);
+    let (lock, mut file) = HostsFile::read_locked(&path).unwrap();
+    let other = {
+        let path = path.to_path_buf();
+        std::thread::spawn(move || {
+            let (_lock, mut file) = HostsFile::read_locked(&path).unwrap();
+            file.add_to_file(&path, &HostsEntry::from((2, 2, 2, 2), "b.domain.com"))
+                .unwrap();
+        })
+    };
+    std::thread::sleep(Duration::from_millis(100));
+    file.add_to_file(&path, &HostsEntry::from((3, 3, 3, 3), "c.domain.com"))
+        .unwrap();
+    drop(lock);
+    other.join().unwrap();
+    let file = HostsFile::read_from_file(&path).unwrap();
+    assert_eq!(file.entries_count(), 3);
+    assert_eq!(HostsFile::undo(&path, 2).unwrap().len(), 2);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), HOSTS);
+}
+
+#[test]
+fn it_fails_to_read_a_missing_file() {
+    let path = std::env::temp_dir().join("hosts-error-missing");
+    let result = HostsFile::read_from_file(&path);
+    assert_eq!(result.unwrap_err().exit_code(), 8);
+}
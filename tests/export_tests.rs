@@ -0,0 +1,102 @@
+use hosts::hostsfile::export::ResolverFormat;
+use hosts::hostsfile::HostsFile;
+
+fn get_file() -> HostsFile {
+    let lines = vec![
+        "1.1.1.1 a.domain.com b.domain.com",
+        "# 3.3.3.3 disabled.domain.com",
+        "2.2.2.2 c.domain.com",
+        "1.1.1.1 a.domain.com",
+    ];
+    HostsFile::read_from_lines(lines.into_iter().map(|line| Ok(String::from(line)))).unwrap()
+}
+
+#[test]
+fn it_parses_formats() {
+    for format in [
+        "dnsmasq",
+        "dnsmasq-host-record",
+        "unbound",
+        "bind-zone",
+        "coredns",
+        "pihole",
+    ] {
+        let parsed: ResolverFormat = format.parse().unwrap();
+        assert_eq!(parsed.to_string(), format);
+    }
+    assert!("hosts".parse::<ResolverFormat>().is_err());
+}
+
+#[test]
+fn it_exports_to_dnsmasq() {
+    assert_eq!(
+        get_file().export(ResolverFormat::Dnsmasq),
+        vec![
+            "address=/a.domain.com/1.1.1.1",
+            "address=/b.domain.com/1.1.1.1",
+            "address=/c.domain.com/2.2.2.2",
+        ]
+    );
+    assert_eq!(
+        get_file().export(ResolverFormat::DnsmasqHostRecord),
+        vec![
+            "host-record=a.domain.com,1.1.1.1",
+            "host-record=b.domain.com,1.1.1.1",
+            "host-record=c.domain.com,2.2.2.2",
+        ]
+    );
+}
+
+#[test]
+fn it_exports_to_unbound() {
+    assert_eq!(
+        get_file().export(ResolverFormat::Unbound),
+        vec![
+            "server:",
+            "    local-data: \"a.domain.com. 3600 IN A 1.1.1.1\"",
+            "    local-data: \"b.domain.com. 3600 IN A 1.1.1.1\"",
+            "    local-data: \"c.domain.com. 3600 IN A 2.2.2.2\"",
+            "    local-data-ptr: \"1.1.1.1 3600 a.domain.com.\"",
+            "    local-data-ptr: \"2.2.2.2 3600 c.domain.com.\"",
+        ]
+    );
+}
+
+#[test]
+fn it_exports_to_a_bind_zone() {
+    assert_eq!(
+        get_file().export(ResolverFormat::BindZone),
+        vec![
+            "$TTL 3600",
+            "a.domain.com. IN A 1.1.1.1",
+            "b.domain.com. IN A 1.1.1.1",
+            "c.domain.com. IN A 2.2.2.2",
+            "1.1.1.1.in-addr.arpa. IN PTR a.domain.com.",
+            "2.2.2.2.in-addr.arpa. IN PTR c.domain.com.",
+        ]
+    );
+}
+
+#[test]
+fn it_exports_to_coredns_and_pihole() {
+    assert_eq!(
+        get_file().export(ResolverFormat::CoreDns),
+        vec![
+            "hosts {",
+            "    ttl 3600",
+            "    1.1.1.1 a.domain.com",
+            "    1.1.1.1 b.domain.com",
+            "    2.2.2.2 c.domain.com",
+            "    fallthrough",
+            "}",
+        ]
+    );
+    assert_eq!(
+        get_file().export(ResolverFormat::PiHole),
+        vec![
+            "1.1.1.1 a.domain.com",
+            "1.1.1.1 b.domain.com",
+            "2.2.2.2 c.domain.com",
+        ]
+    );
+}
@@ -0,0 +1,65 @@
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::parse::ParsedLine;
+use hosts::hostsfile::HostsFile;
+
+fn get_test_lines() -> Vec<std::io::Result<String>> {
+    vec![
+        Ok(String::from("# section one   ")),
+        Ok(String::from("10.0.0.2   b.domain.com")),
+        Ok(String::from("1.1.1.1 a.domain.com    # keep me")),
+        Ok(String::from("")),
+        Ok(String::from("2.2.2.2 d.domain.com")),
+        Ok(String::from("2.2.2.2 c.domain.com")),
+    ]
+}
+
+#[test]
+fn it_aligns_columns_and_keeps_comments() {
+    let file = HostsFile::read_from_lines(get_test_lines().into_iter()).unwrap();
+    let expected = vec![
+        "# section one",
+        "10.0.0.2 b.domain.com",
+        "1.1.1.1  a.domain.com # keep me",
+        "",
+        "2.2.2.2  d.domain.com",
+        "2.2.2.2  c.domain.com",
+    ];
+    assert_eq!(file.render_formatted(), expected);
+}
+
+#[test]
+fn it_only_rewrites_changed_lines() {
+    let mut file = HostsFile::read_from_lines(get_test_lines().into_iter()).unwrap();
+    let original: Vec<String> = get_test_lines().into_iter().map(Result::unwrap).collect();
+    assert_eq!(file.render(), original);
+    file.lines.push(ParsedLine::Entry(HostsEntry::from(
+        (192, 168, 100, 100),
+        "e.domain.com",
+    )));
+    let mut expected = original.clone();
+    expected.push(String::from("192.168.100.100 e.domain.com"));
+    assert_eq!(file.render(), expected);
+}
+
+#[test]
+fn it_sorts_within_sections() {
+    let mut file = HostsFile::read_from_lines(get_test_lines().into_iter()).unwrap();
+    HostsFile::sort_sections(&mut file.lines);
+    let expected = vec![
+        ParsedLine::Comment(String::from("# section one   ")),
+        ParsedLine::Entry(HostsEntry::from((1, 1, 1, 1), "a.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((10, 0, 0, 2), "b.domain.com")),
+        ParsedLine::Empty,
+        ParsedLine::Entry(HostsEntry::from((2, 2, 2, 2), "c.domain.com")),
+        ParsedLine::Entry(HostsEntry::from((2, 2, 2, 2), "d.domain.com")),
+    ];
+    assert_eq!(file.lines, expected);
+}
+
+#[test]
+fn it_counts_ip_widths_without_formatting() {
+    for ip in ["0.0.0.0", "10.20.30.40", "255.255.255.255", "1.100.9.99"] {
+        let parsed = ip.parse().unwrap();
+        assert_eq!(HostsFile::ip_width(&parsed), ip.len());
+    }
+}
@@ -4,6 +4,8 @@ use hosts::hostsfile::parse::ParsedLine;
 use hosts::hostsfile::remove::RemoveResult;
 use hosts::hostsfile::HostsFile;
 
+mod common;
+
 fn get_test_host() -> &'static str {
     "host.domain.com"
 }
@@ -75,10 +77,76 @@ fn it_removes_entry_by_host() {
         ParsedLine::Entry(get_test_entry()),
     ];
     let expected = vec![ParsedLine::Comment(String::from("# comment"))];
-    match HostsFile::remove_from_lines(&mut lines, &get_test_host()) {
-        RemoveResult::RemovedByHost(amount) if amount == 1 => assert_eq!(lines, expected),
+    match HostsFile::remove_from_lines(&mut lines, get_test_host()).unwrap() {
+        RemoveResult::RemovedByHost(1) => assert_eq!(lines, expected),
         RemoveResult::RemovedByHost(amount) => panic!("Removed {} hosts instead of 1", amount),
         RemoveResult::RemovedByIp(amount) => panic!("Removed {} IPs instead of 1 host", amount),
-        RemoveResult::Invalid => panic!("Entry is invalid"),
     }
 }
+
+#[test]
+fn it_removes_entry_by_unicode_host() {
+    let mut lines = vec![ParsedLine::Entry(HostsEntry::from(
+        (1, 1, 1, 1),
+        "xn--bcher-kva.domain.com",
+    ))];
+    match HostsFile::remove_from_lines(&mut lines, "bücher.domain.com").unwrap() {
+        RemoveResult::RemovedByHost(1) => assert!(lines.is_empty()),
+        result => panic!("{:?} instead of 1 host removed", result),
+    }
+}
+
+#[test]
+fn it_shows_hosts_in_unicode() {
+    let entry = HostsEntry::parse("1.1.1.1", "bücher.domain.com").unwrap();
+    assert_eq!(entry.host.to_string(), "xn--bcher-kva.domain.com");
+    assert_eq!(HostsEntry::unicode_host(&entry.host), "bücher.domain.com");
+}
+
+#[test]
+fn it_reads_entries_to_add_from_lines() {
+    let lines = vec![
+        String::from("1.1.1.1 a.domain.com b.domain.com"),
+        String::from(""),
+        String::from("# comment"),
+        String::from("2.2.2.2 c.domain.com"),
+    ];
+    assert_eq!(
+        HostsFile::entries_from_lines(&lines).unwrap(),
+        vec![
+            HostsEntry::from((1, 1, 1, 1), "a.domain.com"),
+            HostsEntry::from((1, 1, 1, 1), "b.domain.com"),
+            HostsEntry::from((2, 2, 2, 2), "c.domain.com"),
+        ]
+    );
+    let lines = vec![String::from("2.2.2.2 c.domain.com"), String::from("nope")];
+    assert!(HostsFile::entries_from_lines(&lines).is_err());
+}
+
+#[test]
+fn it_counts_add_results() {
+    let actions = vec![
+        AddResult::Added,
+        AddResult::Skipped,
+        AddResult::Added,
+        AddResult::Updated(get_test_entry().ip),
+    ];
+    assert_eq!(HostsFile::count_actions(&actions), (2, 1, 1));
+}
+
+#[test]
+fn it_sums_up_a_single_added_entry_like_several() {
+    let path = common::temp_hosts("1.1.1.1 a.domain.com\n");
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_hosts"))
+        .args([
+            "--path",
+            path.to_str().unwrap(),
+            "add",
+            "2.2.2.2",
+            "b.domain.com",
+        ])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("1 added, 0 updated, 0 already existed"));
+}
@@ -0,0 +1,111 @@
+use hosts::error::HostsError;
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::export::ResolverFormat;
+use hosts::hostsfile::HostsFile;
+use hosts::validation::Validation;
+
+mod common;
+
+const HOSTS: &str = "1.1.1.1 a.domain.com b.domain.com\n";
+
+fn to_lines(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|line| String::from(*line)).collect()
+}
+
+#[test]
+fn it_parses_dnsmasq_directives() {
+    let lines = to_lines(&[
+        "# address=/commented.domain.com/9.9.9.9",
+        "address=/a.domain.com/c.domain.com/2.2.2.2",
+        "host-record=d.domain.com,3.3.3.3,fe80::1,300",
+        "address=/v6.domain.com/fe80::1",
+        "server=8.8.8.8",
+    ]);
+    let (entries, skipped, partial) =
+        HostsFile::parse_resolver_config(ResolverFormat::Dnsmasq, &lines).unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            HostsEntry::from((2, 2, 2, 2), "a.domain.com"),
+            HostsEntry::from((2, 2, 2, 2), "c.domain.com"),
+            HostsEntry::from((3, 3, 3, 3), "d.domain.com"),
+        ]
+    );
+    assert_eq!(skipped, vec![4]);
+    assert_eq!(partial, vec![2]);
+}
+
+#[test]
+fn it_parses_unbound_directives() {
+    let lines = to_lines(&[
+        "server:",
+        "    local-data: \"a.domain.com. 3600 IN A 2.2.2.2\"",
+        "    local-data: 'c.domain.com A 3.3.3.3' # comment",
+        "    local-data: \"v6.domain.com. AAAA fe80::1\"",
+        "    local-data-ptr: \"2.2.2.2 a.domain.com\"",
+    ]);
+    let (entries, skipped, partial) =
+        HostsFile::parse_resolver_config(ResolverFormat::Unbound, &lines).unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            HostsEntry::from((2, 2, 2, 2), "a.domain.com"),
+            HostsEntry::from((3, 3, 3, 3), "c.domain.com"),
+        ]
+    );
+    assert_eq!(skipped, vec![4]);
+    assert!(partial.is_empty());
+}
+
+#[test]
+fn it_checks_imported_hostnames() {
+    let lines = to_lines(&[
+        "address=/a.domain.com/2.2.2.2",
+        "address=/_dmarc.test/3.3.3.3",
+    ]);
+    let (entries, _, _) = HostsFile::parse_resolver_config_with(
+        ResolverFormat::Dnsmasq,
+        &lines,
+        Validation::Resolver,
+    )
+    .unwrap();
+    assert_eq!(entries.len(), 2);
+    assert!(matches!(
+        HostsFile::parse_resolver_config_with(ResolverFormat::Dnsmasq, &lines, Validation::Strict),
+        Err(HostsError::Validation(reason)) if reason.contains("line 2")
+    ));
+}
+
+#[test]
+fn it_refuses_formats_without_import() {
+    assert!(matches!(
+        HostsFile::parse_resolver_config(ResolverFormat::BindZone, &[]),
+        Err(HostsError::Parse(_))
+    ));
+}
+
+#[test]
+fn it_imports_with_add_semantics() {
+    let path = common::temp_hosts(HOSTS);
+    let entries = vec![
+        HostsEntry::from((1, 1, 1, 1), "a.domain.com"),
+        HostsEntry::from((2, 2, 2, 2), "b.domain.com"),
+        HostsEntry::from((3, 3, 3, 3), "c.domain.com"),
+    ];
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    let actions = file
+        .add_all_to_file(&path, &entries, "import dnsmasq test")
+        .unwrap();
+    assert_eq!(HostsFile::count_actions(&actions), (1, 1, 1));
+    assert_eq!(
+        HostsFile::read_from_file(&path).unwrap().render(),
+        vec![
+            "1.1.1.1 a.domain.com",
+            "2.2.2.2 b.domain.com",
+            "3.3.3.3 c.domain.com",
+        ]
+    );
+    let history = HostsFile::history(&path).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].operation, "import dnsmasq test");
+}
@@ -0,0 +1,69 @@
+use fqdn::fqdn;
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::indexed::IndexedHostsFile;
+use hosts::hostsfile::HostsFile;
+
+fn get_test_file() -> HostsFile {
+    let lines = vec![
+        Ok(String::from("# comment")),
+        Ok(String::from(
+            "1.1.1.1 a.domain.com b.domain.com c.domain.com",
+        )),
+        Ok(String::from("2.2.2.2 d.domain.com")),
+        Ok(String::from("")),
+        Ok(String::from("2.2.2.2 b.domain.com")),
+        Ok(String::from("3.3.3.3 e.domain.com # comment")),
+    ];
+    HostsFile::read_from_lines(lines.into_iter()).unwrap()
+}
+
+#[test]
+fn it_adds_like_add_to_lines() {
+    let entries = [
+        HostsEntry::from((1, 1, 1, 1), "a.domain.com"),
+        HostsEntry::from((9, 9, 9, 9), "b.domain.com"),
+        HostsEntry::from((8, 8, 8, 8), "c.domain.com"),
+        HostsEntry::from((7, 7, 7, 7), "b.domain.com"),
+        HostsEntry::from((4, 4, 4, 4), "f.domain.com"),
+        HostsEntry::from((5, 5, 5, 5), "f.domain.com"),
+    ];
+    let mut file = get_test_file();
+    let mut indexed = IndexedHostsFile::from(get_test_file());
+    for entry in entries.iter() {
+        let expected = HostsFile::add_to_lines(&mut file.lines, entry);
+        let result = indexed.add(entry);
+        assert_eq!(format!("{:?}", result), format!("{:?}", expected));
+    }
+    assert_eq!(indexed.into_file(), file);
+}
+
+#[test]
+fn it_removes_like_remove_from_lines() {
+    let removals = [
+        "b.domain.com",
+        "2.2.2.2",
+        "a.domain.com",
+        "missing.domain.com",
+        "!",
+    ];
+    let mut file = get_test_file();
+    let mut indexed = IndexedHostsFile::from(get_test_file());
+    for removal in removals {
+        let expected = HostsFile::remove_from_lines(&mut file.lines, removal);
+        let result = indexed.remove(removal);
+        assert_eq!(format!("{:?}", result), format!("{:?}", expected));
+    }
+    assert_eq!(indexed.into_file(), file);
+}
+
+#[test]
+fn it_keeps_lookups_up_to_date() {
+    let mut indexed = IndexedHostsFile::from(get_test_file());
+    indexed.add(&HostsEntry::from((9, 9, 9, 9), "a.domain.com"));
+    let found = indexed.find_by_host(&fqdn!("a.domain.com"));
+    assert_eq!(found, vec![&HostsEntry::from((9, 9, 9, 9), "a.domain.com")]);
+    assert_eq!(indexed.find_by_ip(&"2.2.2.2".parse().unwrap()).len(), 2);
+    indexed.remove("2.2.2.2").unwrap();
+    assert!(indexed.find_by_ip(&"2.2.2.2".parse().unwrap()).is_empty());
+    assert!(indexed.find_by_host(&fqdn!("d.domain.com")).is_empty());
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::journal::{Journal, JournalRecord, JournalState};
+use hosts::hostsfile::HostsFile;
+
+mod common;
+
+const HOSTS: &str = "# comment\n1.1.1.1 first.domain.com\n";
+
+fn read_lines(path: &PathBuf) -> Vec<String> {
+    HostsFile::read_from_file(path).unwrap().render()
+}
+
+#[test]
+fn it_records_only_the_changed_lines() {
+    let before = vec![
+        String::from("# comment"),
+        String::from("1.1.1.1 first.domain.com"),
+        String::from(""),
+    ];
+    let after = vec![
+        String::from("# comment"),
+        String::from("2.2.2.2 first.domain.com"),
+        String::from(""),
+    ];
+    let record = JournalRecord::new("add", "Updated", &before, &after);
+    assert_eq!(record.start, 1);
+    assert_eq!(
+        record.before,
+        vec![String::from("1.1.1.1 first.domain.com")]
+    );
+    assert_eq!(record.after, vec![String::from("2.2.2.2 first.domain.com")]);
+}
+
+#[test]
+fn it_undoes_and_redoes_changes() {
+    let path = common::temp_hosts(HOSTS);
+    let original = read_lines(&path);
+    let entry = HostsEntry::from((2, 2, 2, 2), "second.domain.com");
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.add_to_file(&path, &entry).unwrap();
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.remove_from_file(&path, "first.domain.com").unwrap();
+    let changed = read_lines(&path);
+
+    let history = HostsFile::history(&path).unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].operation, "add 2.2.2.2 second.domain.com");
+
+    let undone = HostsFile::undo(&path, 2).unwrap();
+    assert_eq!(undone.len(), 2);
+    assert_eq!(read_lines(&path), original);
+    assert!(HostsFile::history(&path)
+        .unwrap()
+        .iter()
+        .all(|record| record.state == JournalState::Undone));
+
+    let redone = HostsFile::redo(&path, 5).unwrap();
+    assert_eq!(redone.len(), 2);
+    assert_eq!(read_lines(&path), changed);
+}
+
+#[test]
+fn it_refuses_to_undo_when_the_file_changed() {
+    let path = common::temp_hosts(HOSTS);
+    let entry = HostsEntry::from((2, 2, 2, 2), "second.domain.com");
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.add_to_file(&path, &entry).unwrap();
+    std::fs::write(&path, "3.3.3.3 other.domain.com\n").unwrap();
+
+    assert!(HostsFile::undo(&path, 1).is_err());
+    assert_eq!(
+        read_lines(&path),
+        vec![String::from("3.3.3.3 other.domain.com")]
+    );
+}
+
+#[test]
+fn it_drops_undone_changes_once_a_new_one_is_made() {
+    let path = common::temp_hosts(HOSTS);
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.add_to_file(&path, &HostsEntry::from((2, 2, 2, 2), "second.domain.com"))
+        .unwrap();
+    HostsFile::undo(&path, 1).unwrap();
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.add_to_file(&path, &HostsEntry::from((3, 3, 3, 3), "third.domain.com"))
+        .unwrap();
+
+    let history = HostsFile::history(&path).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].operation, "add 3.3.3.3 third.domain.com");
+    assert_eq!(history[0].state, JournalState::Applied);
+    assert!(HostsFile::redo(&path, 1).unwrap().is_empty());
+}
+
+#[test]
+fn it_trims_the_oldest_changes() {
+    let before = vec![String::from("1.1.1.1 first.domain.com")];
+    let after = vec![String::from("2.2.2.2 first.domain.com")];
+    let mut journal = Journal {
+        records: (0..10)
+            .map(|index| JournalRecord::new(&format!("add {}", index), "Updated", &before, &after))
+            .collect(),
+    };
+    journal.trim(300).unwrap();
+    assert!(!journal.records.is_empty() && journal.records.len() < 10);
+    assert_eq!(journal.records.last().unwrap().operation, "add 9");
+
+    journal.trim(0).unwrap();
+    assert_eq!(journal.records.len(), 1);
+    assert_eq!(journal.records[0].operation, "add 9");
+}
@@ -1,6 +1,7 @@
-use hosts::hostsfile::HostsFile;
 use hosts::hostsentry::HostsEntry;
 use hosts::hostsfile::parse::ParsedLine;
+use hosts::hostsfile::HostsFile;
+use hosts::validation::Validation;
 
 fn get_test_line() -> ParsedLine {
     ParsedLine::Entry(HostsEntry::from((1, 2, 3, 4), "host.domain.com"))
@@ -82,7 +83,7 @@ fn it_parses_invalid_ip() {
 #[test]
 fn it_parses_invalid_host() {
     let line = "1.2.3.4 invalid";
-    let result = HostsFile::parse_line(line);
+    let result = HostsFile::parse_line_with(line, Validation::Strict);
     let expected = ParsedLine::Invalid(String::from(line));
     assert_eq!(result, expected);
 }
@@ -98,7 +99,69 @@ fn it_parses_invalid_starting_with_space() {
 #[test]
 fn it_parses_invalid_with_trailing_text() {
     let line = "1.2.3.4 host.domain.com invalid";
-    let result = HostsFile::parse_line(line);
+    let result = HostsFile::parse_line_with(line, Validation::Strict);
     let expected = ParsedLine::Invalid(String::from(line));
     assert_eq!(result, expected);
 }
+
+#[test]
+fn it_keeps_the_comment_of_entries() {
+    let result = HostsFile::parse_line("1.2.3.4 host.domain.com   # foo bar  ");
+    match result {
+        ParsedLine::Entry(entry) => assert_eq!(entry.comment, Some(String::from("# foo bar"))),
+        _ => panic!("Not parsed as an entry"),
+    }
+}
+
+#[test]
+fn it_parses_entries_with_aliases() {
+    let result = HostsFile::parse_line("1.2.3.4 host.domain.com alias.domain.com # foo");
+    match result {
+        ParsedLine::Entry(entry) => {
+            assert_eq!(entry.host.to_string(), "host.domain.com");
+            assert_eq!(entry.aliases.len(), 1);
+            assert_eq!(entry.aliases[0].to_string(), "alias.domain.com");
+        }
+        _ => panic!("Not parsed as an entry"),
+    }
+}
+
+#[test]
+fn it_parses_internationalized_hosts_as_punycode() {
+    let result = HostsFile::parse_line("1.2.3.4 bücher.domain.com");
+    let expected = ParsedLine::Entry(HostsEntry::from((1, 2, 3, 4), "xn--bcher-kva.domain.com"));
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn it_parses_a_lone_ip_as_invalid() {
+    let result = HostsFile::parse_line("1.2.3.4 # no hostname");
+    assert_eq!(
+        result,
+        ParsedLine::Invalid(String::from("1.2.3.4 # no hostname"))
+    );
+}
+
+#[test]
+fn it_parses_real_world_names_for_resolvers() {
+    for line in [
+        "127.0.0.1 localhost",
+        "1.2.3.4 myhost",
+        "1.2.3.4 _dmarc.test",
+        "1.2.3.4 my_container",
+    ] {
+        let result = HostsFile::parse_line(line);
+        assert!(matches!(result, ParsedLine::Entry(_)), "{}", line);
+        let result = HostsFile::parse_line_with(line, Validation::Strict);
+        assert_eq!(result, ParsedLine::Invalid(String::from(line)));
+    }
+}
+
+#[test]
+fn it_parses_long_labels_only_when_permissive() {
+    let line = format!("0.0.0.0 {}.blocked.test", "a".repeat(64));
+    let result = HostsFile::parse_line(&line);
+    assert_eq!(result, ParsedLine::Invalid(line.clone()));
+    let result = HostsFile::parse_line_with(&line, Validation::Permissive);
+    assert!(matches!(result, ParsedLine::Entry(_)));
+}
@@ -0,0 +1,161 @@
+use hosts::error::HostsError;
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::HostsFile;
+use hosts::policy::{Pattern, Policy, Verdict};
+
+mod common;
+
+fn get_test_policy() -> Policy {
+    Policy::parse(
+        "# never redirect banks\n\
+         deny *.bank.com except 0.0.0.0 127.0.0.1\n\
+         deny sso.corp.com\n\
+         allow *.test\n\
+         allow *.localhost # local services\n",
+    )
+    .unwrap()
+}
+
+#[test]
+fn it_matches_patterns() {
+    let pattern: Pattern = "*.bank.com".parse().unwrap();
+    assert!(pattern.matches("bank.com"));
+    assert!(pattern.matches("WWW.Bank.com"));
+    assert!(!pattern.matches("notbank.com"));
+    let pattern: Pattern = "bücher.test".parse().unwrap();
+    assert!(pattern.matches("xn--bcher-kva.test"));
+    assert!(!pattern.matches("www.xn--bcher-kva.test"));
+    let pattern: Pattern = "*".parse().unwrap();
+    assert!(pattern.matches("anything.com"));
+}
+
+#[test]
+fn it_checks_entries_against_the_rules() {
+    let policy = get_test_policy();
+    let check = |ip: (u8, u8, u8, u8), host: &str| policy.check(&HostsEntry::from(ip, host));
+    assert_eq!(check((0, 0, 0, 0), "www.bank.com"), Verdict::Allowed);
+    assert!(matches!(
+        check((1, 2, 3, 4), "www.bank.com"),
+        Verdict::Denied(_)
+    ));
+    assert!(matches!(
+        check((0, 0, 0, 0), "sso.corp.com"),
+        Verdict::Denied(_)
+    ));
+    assert_eq!(check((1, 2, 3, 4), "app.test"), Verdict::Allowed);
+    assert!(matches!(
+        check((1, 2, 3, 4), "example.com"),
+        Verdict::Unlisted(_)
+    ));
+    assert_eq!(
+        Policy::default().check(&HostsEntry::from((1, 2, 3, 4), "example.com")),
+        Verdict::Allowed
+    );
+}
+
+#[test]
+fn it_warns_about_unlisted_entries_and_refuses_denied_ones() {
+    let policy = get_test_policy();
+    let unlisted = [HostsEntry::from((1, 2, 3, 4), "example.com")];
+    assert_eq!(policy.enforce(&unlisted).unwrap().len(), 1);
+    let allowed = [HostsEntry::from((1, 2, 3, 4), "app.test")];
+    assert!(policy.enforce(&allowed).unwrap().is_empty());
+    let denied = [HostsEntry::from((1, 2, 3, 4), "login.bank.com")];
+    assert!(matches!(
+        policy.enforce(&denied),
+        Err(HostsError::Validation(_))
+    ));
+}
+
+#[test]
+fn it_allows_local_domains_by_default() {
+    let policy = Policy::defaults();
+    let check = |host: &str| policy.check(&HostsEntry::from((1, 2, 3, 4), host));
+    assert_eq!(check("app.test"), Verdict::Allowed);
+    assert_eq!(check("api.localhost"), Verdict::Allowed);
+    assert_eq!(check("db.internal"), Verdict::Allowed);
+    assert!(matches!(check("example.com"), Verdict::Unlisted(_)));
+}
+
+#[test]
+fn it_reviews_only_new_mappings() {
+    let policy = get_test_policy();
+    let lines = |lines: &[&str]| -> Vec<String> { lines.iter().map(|l| l.to_string()).collect() };
+    let before = lines(&["1.2.3.4 example.com", "0.0.0.0 www.bank.com"]);
+    let disabled = lines(&["# 1.2.3.4 example.com", "0.0.0.0 www.bank.com"]);
+    assert!(policy.review(&before, &disabled).unwrap().is_empty());
+    let renamed = lines(&["1.2.3.4 example.org", "0.0.0.0 www.bank.com"]);
+    assert_eq!(policy.review(&before, &renamed).unwrap().len(), 1);
+    let retargeted = lines(&["1.2.3.4 example.com", "1.2.3.4 www.bank.com"]);
+    assert!(matches!(
+        policy.review(&before, &retargeted),
+        Err(HostsError::Validation(_))
+    ));
+}
+
+#[test]
+fn it_refuses_denied_entries_on_every_write() {
+    let path = common::temp_hosts("1.1.1.1 a.test\n");
+    std::fs::write(Policy::path_for(&path), "deny *.bank.com\n").unwrap();
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    assert!(matches!(
+        file.rename_in_file(&path, "a.test", "www.bank.com", false),
+        Err(HostsError::Validation(_))
+    ));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "1.1.1.1 a.test\n");
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.retarget_in_file(
+        &path,
+        "1.1.1.1".parse().unwrap(),
+        "2.2.2.2".parse().unwrap(),
+    )
+    .unwrap();
+    assert!(file.warnings.is_empty());
+    file.add_to_file(&path, &HostsEntry::from((3, 3, 3, 3), "example.com"))
+        .unwrap();
+    assert_eq!(file.warnings.len(), 1);
+}
+
+#[test]
+fn it_refuses_to_undo_or_redo_into_denied_entries() {
+    let path = common::temp_hosts("1.1.1.1 a.test\n");
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.add_to_file(&path, &HostsEntry::from((2, 2, 2, 2), "www.bank.com"))
+        .unwrap();
+    let mut file = HostsFile::read_from_file(&path).unwrap();
+    file.remove_from_file(&path, "www.bank.com").unwrap();
+    std::fs::write(Policy::path_for(&path), "deny *.bank.com\n").unwrap();
+    let unchanged = std::fs::read_to_string(&path).unwrap();
+
+    assert!(matches!(
+        HostsFile::undo(&path, 1),
+        Err(HostsError::Validation(_))
+    ));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), unchanged);
+
+    std::fs::remove_file(Policy::path_for(&path)).unwrap();
+    HostsFile::undo(&path, 2).unwrap();
+    std::fs::write(Policy::path_for(&path), "deny *.bank.com\n").unwrap();
+    let unchanged = std::fs::read_to_string(&path).unwrap();
+    assert!(matches!(
+        HostsFile::redo(&path, 1),
+        Err(HostsError::Validation(_))
+    ));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), unchanged);
+}
+
+#[test]
+fn it_rejects_invalid_rules() {
+    for content in [
+        "allow",
+        "deny *.bank.com except",
+        "deny x.com except nope",
+        "block x.com",
+    ] {
+        assert!(
+            matches!(Policy::parse(content), Err(HostsError::Parse(_))),
+            "{}",
+            content
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use hosts::error::HostsError;
+use hosts::hostsfile::profile::ProfileState;
+use hosts::hostsfile::HostsFile;
+
+fn get_test_file(lines: &[&str]) -> HostsFile {
+    HostsFile::read_from_lines(lines.iter().map(|line| Ok(String::from(*line)))).unwrap()
+}
+
+fn get_profiles_file() -> HostsFile {
+    get_test_file(&[
+        "127.0.0.1 localhost",
+        "# profile local",
+        "127.0.0.1 api.domain.com web.domain.com",
+        "# end profile local",
+        "# profile staging",
+        "# 10.0.0.1 api.domain.com",
+        "# 10.0.0.2 web.domain.com",
+        "# end profile staging",
+    ])
+}
+
+#[test]
+fn it_lists_profiles_with_their_state() {
+    let profiles = get_profiles_file().profiles().unwrap();
+    let states: Vec<(&str, ProfileState)> = profiles
+        .iter()
+        .map(|profile| (profile.name.as_str(), profile.state()))
+        .collect();
+    assert_eq!(
+        states,
+        vec![
+            ("local", ProfileState::Active),
+            ("staging", ProfileState::Inactive)
+        ]
+    );
+    assert_eq!((profiles[0].start, profiles[0].end), (1, 3));
+}
+
+#[test]
+fn it_switches_profiles() {
+    let mut file = get_profiles_file();
+    let switched = HostsFile::use_profile_in_lines(&mut file.lines, "staging").unwrap();
+    assert_eq!(switched, (2, 2));
+    assert_eq!(
+        file.render(),
+        vec![
+            "127.0.0.1 localhost",
+            "# profile local",
+            "# 127.0.0.1 api.domain.com web.domain.com",
+            "# end profile local",
+            "# profile staging",
+            "10.0.0.1 api.domain.com",
+            "10.0.0.2 web.domain.com",
+            "# end profile staging",
+        ]
+    );
+    let switched = HostsFile::use_profile_in_lines(&mut file.lines, "staging").unwrap();
+    assert_eq!(switched, (0, 0));
+    let result = HostsFile::use_profile_in_lines(&mut file.lines, "demo");
+    assert!(matches!(result, Err(HostsError::NotFound(_))));
+}
+
+#[test]
+fn it_rejects_broken_sections() {
+    let broken = [
+        vec!["# profile local", "# profile staging"],
+        vec!["# end profile local"],
+        vec!["# profile local", "1.1.1.1 a.domain.com"],
+        vec![
+            "# profile local",
+            "# end profile local",
+            "# profile local",
+            "# end profile local",
+        ],
+    ];
+    for lines in broken {
+        let result = get_test_file(&lines).profiles();
+        assert!(matches!(result, Err(HostsError::Parse(_))), "{:?}", lines);
+    }
+}
+
+#[test]
+fn it_ignores_comments_that_only_start_like_markers() {
+    let file = get_test_file(&[
+        "# profile picture server below",
+        "10.0.0.3 pictures.domain.com",
+        "# profile local",
+        "127.0.0.1 api.domain.com",
+        "# end profile local",
+        "# end profile of the picture server",
+    ]);
+    let profiles = file.profiles().unwrap();
+    assert_eq!(profiles.len(), 1);
+    assert_eq!((profiles[0].name.as_str(), profiles[0].start), ("local", 2));
+}
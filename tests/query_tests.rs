@@ -0,0 +1,99 @@
+use fqdn::fqdn;
+use hosts::cidr::Ipv4Cidr;
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::HostsFile;
+use std::net::Ipv4Addr;
+
+fn get_test_file() -> HostsFile {
+    let lines = vec![
+        Ok(String::from("# comment")),
+        Ok(String::from("10.1.2.3 a.domain.com b.domain.com")),
+        Ok(String::from("invalid line")),
+        Ok(String::from("10.9.9.9 c.domain.com")),
+        Ok(String::from("192.168.1.1 b.domain.com")),
+    ];
+    HostsFile::read_from_lines(lines.into_iter()).unwrap()
+}
+
+#[test]
+fn it_iterates_entries_with_line_indices() {
+    let file = get_test_file();
+    let indices: Vec<usize> = file.iter_entries().map(|(index, _)| index).collect();
+    assert_eq!(indices, vec![1, 3, 4]);
+    let invalids: Vec<usize> = file.iter_invalids().map(|(index, _)| index).collect();
+    assert_eq!(invalids, vec![2]);
+}
+
+#[test]
+fn it_finds_entries_by_host_and_alias() {
+    let file = get_test_file();
+    let host = fqdn!("b.domain.com");
+    let found: Vec<usize> = file.find_by_host(&host).map(|(index, _)| index).collect();
+    assert_eq!(found, vec![1, 4]);
+}
+
+#[test]
+fn it_finds_entries_by_ip() {
+    let file = get_test_file();
+    let found: Vec<&HostsEntry> = file
+        .find_by_ip(Ipv4Addr::new(10, 9, 9, 9))
+        .map(|(_, entry)| entry)
+        .collect();
+    assert_eq!(
+        found,
+        vec![&HostsEntry::from((10, 9, 9, 9), "c.domain.com")]
+    );
+}
+
+#[test]
+fn it_finds_entries_in_a_network() {
+    let file = get_test_file();
+    let network = "10.0.0.0/8".parse::<Ipv4Cidr>().unwrap();
+    let found: Vec<usize> = file
+        .find_in_network(network)
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(found, vec![1, 3]);
+}
+
+#[test]
+fn it_parses_cidr_notation() {
+    let network = "192.168.1.77/24".parse::<Ipv4Cidr>().unwrap();
+    assert_eq!(network.network, Ipv4Addr::new(192, 168, 1, 0));
+    assert!(network.contains(&Ipv4Addr::new(192, 168, 1, 200)));
+    assert!(!network.contains(&Ipv4Addr::new(192, 168, 2, 1)));
+    assert_eq!("10.0.0.1".parse::<Ipv4Cidr>().unwrap().prefix, 32);
+    assert!("10.0.0.0/33".parse::<Ipv4Cidr>().is_err());
+}
+
+#[test]
+fn it_groups_hostnames_by_ip() {
+    let lines = vec![
+        Ok(String::from("10.9.9.9 z.domain.com")),
+        Ok(String::from("# 10.1.2.3 d.domain.com a.domain.com")),
+        Ok(String::from("10.1.2.3 c.domain.com a.domain.com")),
+        Ok(String::from("10.1.2.3 b.domain.com")),
+    ];
+    let file = HostsFile::read_from_lines(lines.into_iter()).unwrap();
+    let groups = file.group_by_ip();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].ip, Ipv4Addr::new(10, 1, 2, 3));
+    assert_eq!(
+        groups[0].hosts,
+        vec![
+            fqdn!("b.domain.com"),
+            fqdn!("c.domain.com"),
+            fqdn!("a.domain.com")
+        ]
+    );
+    assert_eq!(groups[0].disabled, vec![fqdn!("d.domain.com")]);
+    assert_eq!(groups[1].hosts, vec![fqdn!("z.domain.com")]);
+}
+
+#[test]
+fn it_finds_hostnames_for_an_ip() {
+    let file = get_test_file();
+    let group = file.hosts_for_ip(Ipv4Addr::new(192, 168, 1, 1)).unwrap();
+    assert_eq!(group.hosts, vec![fqdn!("b.domain.com")]);
+    assert!(file.hosts_for_ip(Ipv4Addr::new(1, 1, 1, 1)).is_none());
+}
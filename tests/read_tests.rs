@@ -1,10 +1,14 @@
 use std::path::PathBuf;
 
+use hosts::hostsfile::parse::ParsedLine;
 use hosts::hostsfile::HostsFile;
+use hosts::validation::Validation;
 
 fn get_testfile_path() -> PathBuf {
     let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.push(r"resources\test\hosts.txt");
+    path.push("resources");
+    path.push("test");
+    path.push("hosts.txt");
     path
 }
 
@@ -20,3 +24,60 @@ fn it_reads_hostsfile_correctly() {
     let result = HostsFile::read_from_file(&PathBuf::from(HostsFile::PATH));
     assert!(result.is_ok());
 }
+
+#[test]
+fn it_streams_testfile_lazily() {
+    let mut lines = HostsFile::stream_from_file(&get_testfile_path()).unwrap();
+    let first = lines.next().unwrap().unwrap();
+    assert_eq!(
+        first,
+        ParsedLine::Comment(String::from("# Copyright (c) 1993-2009 Microsoft Corp."))
+    );
+    let entries = lines
+        .filter(|line| matches!(line, Ok(ParsedLine::Entry(_))))
+        .count();
+    assert_eq!(entries, 8);
+}
+
+#[test]
+fn it_streams_filtered_entries_to_a_writer() {
+    let lines = vec![
+        Ok(String::from("# comment")),
+        Ok(String::from("1.1.1.1 keep.domain.com")),
+        Ok(String::from("2.2.2.2 drop.domain.com")),
+    ];
+    let mut output = Vec::new();
+    let written = HostsFile::stream_to(lines.into_iter(), &mut output, |line| match &line {
+        ParsedLine::Entry(entry) if entry.ip.octets()[0] == 2 => None,
+        _ => Some(line),
+    })
+    .unwrap();
+    assert_eq!(written, 2);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "# comment\n1.1.1.1 keep.domain.com\n"
+    );
+}
+
+#[test]
+fn it_streams_with_the_validation_asked_for() {
+    let lines = || vec![Ok(String::from("1.1.1.1 localhost"))].into_iter();
+    let mut output = Vec::new();
+    let written = HostsFile::stream_to(lines(), &mut output, |line| match &line {
+        ParsedLine::Entry(_) => Some(line),
+        _ => None,
+    })
+    .unwrap();
+    assert_eq!(written, 1);
+    let written = HostsFile::stream_to_with(
+        lines(),
+        &mut output,
+        |line| match &line {
+            ParsedLine::Entry(_) => Some(line),
+            _ => None,
+        },
+        Validation::Strict,
+    )
+    .unwrap();
+    assert_eq!(written, 0);
+}
@@ -0,0 +1,105 @@
+use fqdn::fqdn;
+use hosts::error::HostsError;
+use hosts::hostsfile::HostsFile;
+use hosts::validation::Validation;
+use std::path::PathBuf;
+
+fn get_test_file() -> HostsFile {
+    let lines = vec![
+        Ok(String::from("# comment")),
+        Ok(String::from("1.1.1.1 a.domain.com b.domain.com # inline")),
+        Ok(String::from("2.2.2.2 c.domain.com")),
+        Ok(String::from("3.3.3.3 d.domain.com a.domain.com")),
+    ];
+    HostsFile::read_from_lines(lines.into_iter()).unwrap()
+}
+
+#[test]
+fn it_renames_in_place() {
+    let mut file = get_test_file();
+    let result = HostsFile::rename_in_lines(
+        &mut file.lines,
+        &fqdn!("a.domain.com"),
+        &fqdn!("e.domain.com"),
+        false,
+    );
+    assert_eq!(result.unwrap(), 2);
+    assert_eq!(
+        file.render(),
+        vec![
+            "# comment",
+            "1.1.1.1 e.domain.com b.domain.com # inline",
+            "2.2.2.2 c.domain.com",
+            "3.3.3.3 d.domain.com e.domain.com",
+        ]
+    );
+}
+
+#[test]
+fn it_refuses_existing_names_without_merge() {
+    let mut file = get_test_file();
+    let result = HostsFile::rename_in_lines(
+        &mut file.lines,
+        &fqdn!("a.domain.com"),
+        &fqdn!("c.domain.com"),
+        false,
+    );
+    assert!(matches!(result, Err(HostsError::Conflict(_))));
+    assert_eq!(file, get_test_file());
+    let result = HostsFile::rename_in_lines(
+        &mut file.lines,
+        &fqdn!("x.domain.com"),
+        &fqdn!("y.domain.com"),
+        false,
+    );
+    assert!(matches!(result, Err(HostsError::NotFound(_))));
+}
+
+#[test]
+fn it_merges_into_existing_names() {
+    let mut file = get_test_file();
+    let result = HostsFile::rename_in_lines(
+        &mut file.lines,
+        &fqdn!("d.domain.com"),
+        &fqdn!("a.domain.com"),
+        true,
+    );
+    assert_eq!(result.unwrap(), 1);
+    assert_eq!(
+        file.render(),
+        vec![
+            "# comment",
+            "1.1.1.1 a.domain.com b.domain.com # inline",
+            "2.2.2.2 c.domain.com",
+            "3.3.3.3 a.domain.com",
+        ]
+    );
+}
+
+#[test]
+fn it_refuses_merges_onto_another_ip() {
+    let mut file = get_test_file();
+    let result = HostsFile::rename_in_lines(
+        &mut file.lines,
+        &fqdn!("c.domain.com"),
+        &fqdn!("b.domain.com"),
+        true,
+    );
+    assert!(matches!(result, Err(HostsError::Conflict(_))));
+    assert_eq!(file, get_test_file());
+}
+
+#[test]
+fn it_checks_the_new_name() {
+    let mut file = get_test_file();
+    let path = PathBuf::from("/nonexistent/hosts");
+    let result = file.rename_in_file_with(
+        &path,
+        "c.domain.com",
+        "_c.domain.com",
+        false,
+        Validation::Strict,
+    );
+    assert!(matches!(result, Err(HostsError::Validation(_))));
+    assert_eq!(file, get_test_file());
+}
@@ -0,0 +1,53 @@
+use std::net::Ipv4Addr;
+
+use fqdn::fqdn;
+use hosts::hostsfile::HostsFile;
+
+fn get_test_file() -> HostsFile {
+    let lines = vec![
+        Ok(String::from("192.168.56.10 a.domain.com b.domain.com # vm")),
+        Ok(String::from("2.2.2.2 c.domain.com")),
+        Ok(String::from("# 192.168.56.10 disabled.domain.com")),
+        Ok(String::from("192.168.56.10 a.domain.com d.domain.com")),
+    ];
+    HostsFile::read_from_lines(lines.into_iter()).unwrap()
+}
+
+#[test]
+fn it_moves_every_hostname_on_an_ip() {
+    let mut file = get_test_file();
+    let hosts = HostsFile::retarget_in_lines(
+        &mut file.lines,
+        Ipv4Addr::new(192, 168, 56, 10),
+        Ipv4Addr::new(192, 168, 56, 20),
+    );
+    assert_eq!(
+        hosts,
+        vec![
+            fqdn!("a.domain.com"),
+            fqdn!("b.domain.com"),
+            fqdn!("d.domain.com")
+        ]
+    );
+    assert_eq!(
+        file.render(),
+        vec![
+            "192.168.56.20 a.domain.com b.domain.com # vm",
+            "2.2.2.2 c.domain.com",
+            "# 192.168.56.10 disabled.domain.com",
+            "192.168.56.20 a.domain.com d.domain.com",
+        ]
+    );
+}
+
+#[test]
+fn it_leaves_other_ips_alone() {
+    let mut file = get_test_file();
+    let hosts = HostsFile::retarget_in_lines(
+        &mut file.lines,
+        Ipv4Addr::new(9, 9, 9, 9),
+        Ipv4Addr::new(8, 8, 8, 8),
+    );
+    assert!(hosts.is_empty());
+    assert_eq!(file, get_test_file());
+}
@@ -0,0 +1,103 @@
+use hosts::error::HostsError;
+use hosts::hostsfile::template::Variables;
+use hosts::hostsfile::HostsFile;
+
+mod common;
+
+fn get_variables(values: &[(&str, &str)]) -> Variables {
+    Variables {
+        values: values
+            .iter()
+            .map(|(name, value)| (String::from(*name), String::from(*value)))
+            .collect(),
+    }
+}
+
+#[test]
+fn it_substitutes_variables() {
+    let variables = get_variables(&[("CLUSTER_IP", "10.0.0.1"), ("ZONE", "test")]);
+    assert_eq!(
+        variables.substitute("${CLUSTER_IP} app.${ZONE}").unwrap(),
+        "10.0.0.1 app.test"
+    );
+    let result = variables.substitute("${MISSING} app.test");
+    assert!(matches!(result, Err(HostsError::Validation(_))));
+    let result = variables.substitute("${CLUSTER_IP app.test");
+    assert!(matches!(result, Err(HostsError::Parse(_))));
+}
+
+#[test]
+fn it_reads_env_files_without_overriding() {
+    let path =
+        common::temp_hosts("# cluster\nexport CLUSTER_IP=\"10.0.0.1\"\nZONE='test'\nSET=file\n");
+    let mut variables = get_variables(&[("SET", "environment")]);
+    variables.read_env_file(&path).unwrap();
+    assert_eq!(
+        variables,
+        get_variables(&[
+            ("CLUSTER_IP", "10.0.0.1"),
+            ("ZONE", "test"),
+            ("SET", "environment"),
+        ])
+    );
+    std::fs::write(&path, "not a variable\n").unwrap();
+    let result = variables.read_env_file(&path);
+    assert!(matches!(result, Err(HostsError::Parse(_))));
+}
+
+#[test]
+fn it_renders_entries_below_templates() {
+    let lines = [
+        "# template ${CLUSTER_IP} app.test",
+        "1.1.1.1 app.test",
+        "# template ${CLUSTER_IP} api.test # cluster",
+        "# 1.1.1.1 api.test",
+        "# template ${CLUSTER_IP} web.test",
+    ];
+    let mut file =
+        HostsFile::read_from_lines(lines.iter().map(|line| Ok(String::from(*line)))).unwrap();
+    let variables = get_variables(&[("CLUSTER_IP", "10.0.0.1")]);
+    let rendered = HostsFile::render_templates_in_lines(&mut file.lines, &variables).unwrap();
+    assert_eq!(rendered, 3);
+    assert_eq!(
+        file.render(),
+        vec![
+            "# template ${CLUSTER_IP} app.test",
+            "10.0.0.1 app.test",
+            "# template ${CLUSTER_IP} api.test # cluster",
+            "# 10.0.0.1 api.test # cluster",
+            "# template ${CLUSTER_IP} web.test",
+            "10.0.0.1 web.test",
+        ]
+    );
+    let rendered = HostsFile::render_templates_in_lines(&mut file.lines, &variables).unwrap();
+    assert_eq!(rendered, 0);
+}
+
+#[test]
+fn it_keeps_unrelated_entries_below_templates() {
+    let lines = [
+        "# template ${CLUSTER_IP} app.test",
+        "10.0.0.5 db.test",
+        "# template ${CLUSTER_IP} api.test",
+        "# 10.0.0.6 web.test",
+    ];
+    let mut file =
+        HostsFile::read_from_lines(lines.iter().map(|line| Ok(String::from(*line)))).unwrap();
+    let variables = get_variables(&[("CLUSTER_IP", "10.0.0.1")]);
+    let rendered = HostsFile::render_templates_in_lines(&mut file.lines, &variables).unwrap();
+    assert_eq!(rendered, 2);
+    assert_eq!(
+        file.render(),
+        vec![
+            "# template ${CLUSTER_IP} app.test",
+            "10.0.0.1 app.test",
+            "10.0.0.5 db.test",
+            "# template ${CLUSTER_IP} api.test",
+            "10.0.0.1 api.test",
+            "# 10.0.0.6 web.test",
+        ]
+    );
+    let rendered = HostsFile::render_templates_in_lines(&mut file.lines, &variables).unwrap();
+    assert_eq!(rendered, 0);
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "tui")]
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use hosts::error::HostsError;
+use hosts::hostsfile::HostsFile;
+use hosts::tui::{Editor, Mode, RowKind};
+
+mod common;
+
+const HOSTS: &str =
+    "# comment\n1.1.1.1 a.domain.com # inline\n# 2.2.2.2 b.domain.com\ninvalid line\n";
+
+fn press(editor: &mut Editor, keys: &str) {
+    for c in keys.chars() {
+        let code = match c {
+            '\n' => KeyCode::Enter,
+            '\x1b' => KeyCode::Esc,
+            '\x08' => KeyCode::Backspace,
+            c => KeyCode::Char(c),
+        };
+        editor
+            .handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+            .unwrap();
+    }
+}
+
+#[test]
+fn it_highlights_each_kind_of_line() {
+    let path = common::temp_hosts(HOSTS);
+    let mut editor = Editor::open(&path).unwrap();
+    let kinds: Vec<RowKind> = editor.view(10).iter().map(|row| row.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            RowKind::Comment,
+            RowKind::Entry,
+            RowKind::Disabled,
+            RowKind::Invalid
+        ]
+    );
+    assert!(editor.view(10)[0].selected);
+}
+
+#[test]
+fn it_searches_incrementally() {
+    let path = common::temp_hosts(HOSTS);
+    let mut editor = Editor::open(&path).unwrap();
+    press(&mut editor, "/b.dom");
+    assert_eq!(editor.cursor, 2);
+    press(&mut editor, "\x1b");
+    assert_eq!(editor.cursor, 0);
+    press(&mut editor, "/domain\n");
+    assert_eq!(editor.cursor, 1);
+    press(&mut editor, "n");
+    assert_eq!(editor.cursor, 2);
+}
+
+#[test]
+fn it_toggles_and_edits_entries() {
+    let path = common::temp_hosts(HOSTS);
+    let mut editor = Editor::open(&path).unwrap();
+    press(&mut editor, "jj ");
+    assert_eq!(
+        editor.file.render()[2],
+        String::from("2.2.2.2 b.domain.com")
+    );
+    press(&mut editor, "k");
+    press(&mut editor, "e");
+    assert_eq!(
+        editor.mode,
+        Mode::Edit {
+            input: String::from("1.1.1.1 a.domain.com"),
+            new_line: false
+        }
+    );
+    let erase = "\x08".repeat(20);
+    press(&mut editor, &format!("{}1.1.1 c.domain.com\n", erase));
+    assert!(matches!(editor.mode, Mode::Edit { .. }));
+    assert!(editor.message.is_some());
+    press(&mut editor, &format!("{}1.1.1.1 c.domain.com\n", erase));
+    assert_eq!(editor.mode, Mode::Browse);
+    assert_eq!(
+        editor.file.render()[1],
+        String::from("1.1.1.1 c.domain.com # inline")
+    );
+}
+
+#[test]
+fn it_confirms_before_saving() {
+    let path = common::temp_hosts(HOSTS);
+    let mut editor = Editor::open(&path).unwrap();
+    press(&mut editor, "a3.3.3.3 d.domain.com\n");
+    assert_eq!(editor.cursor, 1);
+    press(&mut editor, "s");
+    match &editor.mode {
+        Mode::Confirm(record) => {
+            assert_eq!(record.after, vec![String::from("3.3.3.3 d.domain.com")])
+        }
+        mode => panic!("expected confirmation, got {:?}", mode),
+    }
+    press(&mut editor, "n");
+    assert!(editor.has_changes());
+    press(&mut editor, "q");
+    assert!(!editor.should_quit());
+    press(&mut editor, "sy");
+    assert!(!editor.has_changes());
+    let file = HostsFile::read_from_file(&path).unwrap();
+    assert_eq!(file.render()[1], String::from("3.3.3.3 d.domain.com"));
+    assert_eq!(HostsFile::history(&path).unwrap().len(), 1);
+    press(&mut editor, "q");
+    assert!(editor.should_quit());
+}
+
+#[test]
+fn it_refuses_to_save_over_changes_on_disk() {
+    let path = common::temp_hosts(HOSTS);
+    let mut editor = Editor::open(&path).unwrap();
+    press(&mut editor, "a3.3.3.3 d.domain.com\ns");
+    std::fs::write(&path, "4.4.4.4 e.domain.com\n").unwrap();
+    let result = editor.handle_key(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE));
+    assert!(matches!(result, Err(HostsError::Conflict(_))));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "4.4.4.4 e.domain.com\n"
+    );
+    assert!(editor.has_changes());
+}
@@ -0,0 +1,75 @@
+use hosts::error::HostsError;
+use hosts::hostsentry::HostsEntry;
+use hosts::hostsfile::HostsFile;
+use hosts::validation::Validation;
+
+mod common;
+
+#[test]
+fn it_gives_reasons_for_rejected_names() {
+    let reasons = [
+        ("localhost", "single-label name"),
+        ("_dmarc.domain.com", "contains an underscore"),
+        ("-web.domain.com", "starts or ends with a hyphen"),
+        ("host.123", "all digits"),
+    ];
+    for (host, reason) in reasons {
+        let error = Validation::Strict.check(host).unwrap_err();
+        assert!(error.contains(reason), "{}: {}", host, error);
+        assert_eq!(Validation::Resolver.check(host), Ok(()));
+    }
+    let long_name = vec!["a".repeat(60); 5].join(".");
+    assert!(Validation::Resolver.check(&long_name).is_err());
+    assert_eq!(Validation::Permissive.check(&long_name), Ok(()));
+}
+
+#[test]
+fn it_validates_added_hosts() {
+    let result = HostsEntry::parse_with("1.2.3.4", "myhost", Validation::Strict);
+    assert!(matches!(result, Err(HostsError::Validation(_))));
+    let entry = HostsEntry::parse_with("1.2.3.4", "myhost", Validation::Resolver).unwrap();
+    assert_eq!(entry.host.to_string(), "myhost");
+    let result = HostsEntry::parse_with("1.2.3.4", "bad host", Validation::Permissive);
+    assert!(matches!(result, Err(HostsError::Parse(_))));
+}
+
+#[test]
+fn it_explains_invalid_lines() {
+    let result = HostsFile::try_parse_line("1.2.3.4 _dmarc.test", Validation::Strict);
+    match result {
+        Err(HostsError::Validation(reason)) => assert!(reason.contains("_dmarc")),
+        result => panic!("{:?} instead of a validation error", result),
+    }
+    let result = HostsFile::try_parse_line("1.2.3.4", Validation::Resolver);
+    assert!(matches!(result, Err(HostsError::Parse(_))));
+}
+
+#[test]
+fn it_reads_modes_by_name() {
+    for mode in ["strict", "resolver", "permissive"] {
+        assert_eq!(mode.parse::<Validation>().unwrap().to_string(), mode);
+    }
+    assert!("lenient".parse::<Validation>().is_err());
+}
+
+#[test]
+fn it_looks_up_ips_with_the_validation_asked_for() {
+    let path = common::temp_hosts("10.0.0.1 localhost\n");
+    let hosts = |arguments: &[&str]| {
+        std::process::Command::new(env!("CARGO_BIN_EXE_hosts"))
+            .args(["--path", path.to_str().unwrap()])
+            .args(arguments)
+            .output()
+            .unwrap()
+    };
+    let output = hosts(&["who", "10.0.0.1"]);
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("localhost"));
+    let output = hosts(&["who", "10.0.0.1", "--validation", "strict"]);
+    assert_eq!(output.status.code(), Some(4));
+    let output = hosts(&["list", "--by-ip", "--validation", "strict"]);
+    assert!(String::from_utf8(output.stdout)
+        .unwrap()
+        .contains("Found 0 IPs"));
+}